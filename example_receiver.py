@@ -7,15 +7,37 @@ LiveBox 推送消息接收服务器示例
 from flask import Flask, request, jsonify
 from flask_cors import CORS
 from datetime import datetime
+import hashlib
+import hmac
 import json
+import os
+import time
 
 app = Flask(__name__)
 CORS(app)  # 启用 CORS 支持，允许跨域请求
 
+# 推送签名密钥，和 LiveBox 设置里的签名密钥保持一致；为空时不校验签名
+LIVEBOX_SECRET = os.environ.get('LIVEBOX_SECRET', '')
+# 允许的时间误差（秒），超过的请求视为重放
+SIGNATURE_TOLERANCE = 300
+
 # 消息处理器字典
 message_handlers = {}
 
 
+def verify_signature(body, timestamp, signature):
+    """校验 X-LiveBox-Timestamp / X-LiveBox-Signature 请求头"""
+    try:
+        if abs(time.time() - int(timestamp)) > SIGNATURE_TOLERANCE:
+            return False
+    except (TypeError, ValueError):
+        return False
+    expected = hmac.new(
+        LIVEBOX_SECRET.encode(), f"{timestamp}.".encode() + body, hashlib.sha256
+    ).hexdigest()
+    return hmac.compare_digest(f"sha256={expected}", signature or '')
+
+
 def register_handler(msg_type):
     """装饰器：注册消息处理器"""
     def decorator(func):
@@ -86,6 +108,14 @@ def webhook():
     }
     """
     try:
+        # 配置了密钥时先校验签名
+        if LIVEBOX_SECRET and not verify_signature(
+            request.get_data(),
+            request.headers.get('X-LiveBox-Timestamp'),
+            request.headers.get('X-LiveBox-Signature'),
+        ):
+            return jsonify({"error": "Invalid signature"}), 401
+
         # 获取请求数据
         message = request.get_json()
 
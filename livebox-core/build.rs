@@ -0,0 +1,16 @@
+fn main() {
+    // 使用 prost 编译前端共用的 dy.proto，生成 Rust 版本的消息结构
+    // analysis/dy.proto 是抓包分析时的旧副本，缺少 ControlMessage、MessageHead 等，以 src/proto 这份为准
+    // protoc 由 protoc-bin-vendored 提供，不需要在系统里单独安装
+    println!("cargo:rerun-if-changed=../src/proto/dy.proto");
+    std::env::set_var(
+        "PROTOC",
+        protoc_bin_vendored::protoc_bin_path().expect("找不到内置的 protoc"),
+    );
+    // 生成的结构体同时支持序列化成 JSON，字段名和前端 protobufjs 解码出来的保持一致
+    prost_build::Config::new()
+        .type_attribute(".", "#[derive(serde::Serialize)]")
+        .type_attribute(".", "#[serde(rename_all = \"camelCase\")]")
+        .compile_protos(&["../src/proto/dy.proto"], &["../src/proto"])
+        .expect("编译 dy.proto 失败");
+}
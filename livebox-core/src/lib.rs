@@ -0,0 +1,26 @@
+// LiveBox 核心库，不依赖 Tauri，桌面端、命令行版本和其它服务都可以直接使用
+//
+// - resolver: 把各种形式的直播间地址解析成 web_rid 和 room_id
+// - room: 从直播间页面解析出 RoomInfo / Anchor / StreamUrl
+// - runner: 获取直播间信息（room_info、ttwid、unique_id）
+// - signature: 生成弹幕连接需要的签名
+// - stream: 各个清晰度的 FLV / HLS 拉流地址
+// - webcast: 弹幕 WebSocket 连接、抓包和回放
+// - proto / model: protobuf 解码和结构化的事件
+// - recorder: 录制直播流，按大小或时长分段，旁路记录弹幕
+// - watcher: 定时检查关注的直播间，开播、下播时发出通知
+// - utils: Cookie、存档（storage 特性）、推送（forwarding 特性）
+// - server: 本地广播服务（server 特性）
+pub mod model;
+pub mod proto;
+pub mod recorder;
+pub mod resolver;
+pub mod room;
+pub mod runner;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod signature;
+pub mod stream;
+pub mod utils;
+pub mod watcher;
+pub mod webcast;
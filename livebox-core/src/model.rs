@@ -0,0 +1,596 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+
+use crate::proto::{self, douyin};
+use crate::room::{RoomInfo, RoomStatus};
+use crate::stream::StreamSources;
+
+// 自定义返回的消息
+// room_info 是原始 JSON 给前端用，room 是解析好的直播间信息
+#[derive(serde::Serialize)]
+pub struct LiveInfo {
+    pub room_info: String,
+    pub room: RoomInfo,
+    pub status: RoomStatus,
+    // 各个清晰度的拉流地址，没有在直播时为空
+    pub streams: StreamSources,
+    pub ttwid: String,
+    pub unique_id: String,
+}
+
+// 错误类型常量
+pub const ERROR_ACCESS_DENIED: &str = "ACCESS_DENIED_NEED_LOGIN";
+pub const ERROR_CAPTCHA_REQUIRED: &str = "CAPTCHA_REQUIRED";
+
+// LiveEvent 的结构版本，字段有不兼容的改动时递增
+pub const LIVE_EVENT_VERSION: u32 = 1;
+
+// 推送给前端和外部工具的事件，带上结构版本号和来自哪个直播间
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LiveEventPayload {
+    pub version: u32,
+    pub room_id: String,
+    pub event: LiveEvent,
+}
+
+impl LiveEventPayload {
+    pub fn new(room_id: &str, event: LiveEvent) -> Self {
+        LiveEventPayload {
+            version: LIVE_EVENT_VERSION,
+            room_id: room_id.to_string(),
+            event,
+        }
+    }
+}
+
+// 事件里的用户信息，只保留常用字段；id 用字符串避免前端丢失精度
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct EventUser {
+    pub id: String,
+    pub sec_uid: String,
+    pub display_id: String,
+    pub nick_name: String,
+    pub avatar: String,
+}
+
+impl From<&douyin::User> for EventUser {
+    fn from(user: &douyin::User) -> Self {
+        let id = if user.id_str.is_empty() {
+            user.id.to_string()
+        } else {
+            user.id_str.clone()
+        };
+        EventUser {
+            id,
+            sec_uid: user.sec_uid.clone(),
+            display_id: user.display_id.clone(),
+            nick_name: user.nick_name.clone(),
+            avatar: user
+                .avatar_thumb
+                .as_ref()
+                .and_then(|image| image.url_list_list.first().cloned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// 直播间消息，对应前端 handleMessage 里按 method 区分的各种消息
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    // 聊天弹幕 WebcastChatMessage
+    Chat {
+        msg_id: String,
+        user: EventUser,
+        content: String,
+    },
+    // 礼物 WebcastGiftMessage
+    Gift {
+        msg_id: String,
+        user: EventUser,
+        gift_id: u64,
+        gift_name: String,
+        diamond_count: u32,
+        repeat_count: u64,
+        combo_count: u64,
+        group_count: u64,
+        repeat_end: bool,
+        // 连击礼物会连续发多条消息，repeat_count 是累计数量
+        combo: bool,
+    },
+    // 点赞 WebcastLikeMessage
+    Like {
+        msg_id: String,
+        user: EventUser,
+        count: u64,
+        total: u64,
+    },
+    // 进入直播间 WebcastMemberMessage
+    Member {
+        msg_id: String,
+        user: EventUser,
+        member_count: u64,
+        action: u64,
+    },
+    // 关注 WebcastSocialMessage
+    Follow {
+        msg_id: String,
+        user: EventUser,
+        action: u64,
+        follow_count: u64,
+    },
+    // 直播间统计 WebcastRoomUserSeqMessage
+    RoomUserSeq {
+        msg_id: String,
+        total: i64,
+        total_user: i64,
+        online_user_for_anchor: String,
+        total_pv_for_anchor: String,
+    },
+    // 粉丝票 WebcastUpdateFanTicketMessage
+    FanTicket {
+        msg_id: String,
+        count: u64,
+        count_text: String,
+    },
+    // 公共文本 WebcastCommonTextMessage
+    CommonText {
+        msg_id: String,
+        user: Option<EventUser>,
+        scene: String,
+        describe: String,
+    },
+    // 商品改变 WebcastProductChangeMessage
+    ProductChange {
+        msg_id: String,
+        update_timestamp: i64,
+        update_toast: String,
+        total: i64,
+    },
+    // 对战分数 WebcastMatchAgainstScoreMessage
+    MatchAgainstScore {
+        msg_id: String,
+        left_name: String,
+        left_goal: String,
+        right_name: String,
+        right_goal: String,
+        match_status: u32,
+    },
+    // 直播间人气 WebcastRoomStatsMessage
+    RoomStats {
+        msg_id: String,
+        display_short: String,
+        display_long: String,
+        display_value: i64,
+        total: i64,
+    },
+    // 还没有解析的消息，原样保留 payload，序列化为 base64 字符串
+    Unknown {
+        method: String,
+        #[serde(serialize_with = "serialize_base64")]
+        raw: Vec<u8>,
+    },
+}
+
+impl LiveEvent {
+    /// 事件类型，和序列化后的 type 字段一致
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LiveEvent::Chat { .. } => "chat",
+            LiveEvent::Gift { .. } => "gift",
+            LiveEvent::Like { .. } => "like",
+            LiveEvent::Member { .. } => "member",
+            LiveEvent::Follow { .. } => "follow",
+            LiveEvent::RoomUserSeq { .. } => "room_user_seq",
+            LiveEvent::FanTicket { .. } => "fan_ticket",
+            LiveEvent::CommonText { .. } => "common_text",
+            LiveEvent::ProductChange { .. } => "product_change",
+            LiveEvent::MatchAgainstScore { .. } => "match_against_score",
+            LiveEvent::RoomStats { .. } => "room_stats",
+            LiveEvent::Unknown { .. } => "unknown",
+        }
+    }
+
+    /// Common 里的 msgId，Unknown 没有解码所以没有
+    pub fn msg_id(&self) -> Option<&str> {
+        match self {
+            LiveEvent::Chat { msg_id, .. }
+            | LiveEvent::Gift { msg_id, .. }
+            | LiveEvent::Like { msg_id, .. }
+            | LiveEvent::Member { msg_id, .. }
+            | LiveEvent::Follow { msg_id, .. }
+            | LiveEvent::RoomUserSeq { msg_id, .. }
+            | LiveEvent::FanTicket { msg_id, .. }
+            | LiveEvent::CommonText { msg_id, .. }
+            | LiveEvent::ProductChange { msg_id, .. }
+            | LiveEvent::MatchAgainstScore { msg_id, .. }
+            | LiveEvent::RoomStats { msg_id, .. } => Some(msg_id),
+            LiveEvent::Unknown { .. } => None,
+        }
+    }
+
+    /// 发送这条消息的用户，统计类消息没有用户
+    pub fn user(&self) -> Option<&EventUser> {
+        match self {
+            LiveEvent::Chat { user, .. }
+            | LiveEvent::Gift { user, .. }
+            | LiveEvent::Like { user, .. }
+            | LiveEvent::Member { user, .. }
+            | LiveEvent::Follow { user, .. } => Some(user),
+            LiveEvent::CommonText { user, .. } => user.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// 消息里的文字内容：弹幕内容、礼物描述等
+    pub fn content(&self) -> Option<String> {
+        match self {
+            LiveEvent::Chat { content, .. } => Some(content.clone()),
+            LiveEvent::Gift {
+                gift_name,
+                repeat_count,
+                ..
+            } => Some(format!("送出{} x{}个", gift_name, repeat_count)),
+            LiveEvent::CommonText { describe, .. } => Some(describe.clone()),
+            LiveEvent::ProductChange { update_toast, .. } => Some(update_toast.clone()),
+            _ => None,
+        }
+    }
+
+    /// 把 Response.messagesList 中的一条消息转换为事件，解码失败时返回 Unknown
+    pub fn from_message(message: &proto::Message) -> Self {
+        match Self::decode(message) {
+            Ok(Some(event)) => event,
+            Ok(None) => Self::unknown(message),
+            Err(e) => {
+                eprintln!("⚠️ 解析 {} 失败: {}", message.method, e);
+                Self::unknown(message)
+            }
+        }
+    }
+
+    fn unknown(message: &proto::Message) -> Self {
+        LiveEvent::Unknown {
+            method: message.method.clone(),
+            raw: message.payload.clone(),
+        }
+    }
+
+    fn decode(
+        message: &proto::Message,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error + Send + Sync>> {
+        let event = match message.method.as_str() {
+            "WebcastChatMessage" => {
+                let msg: douyin::ChatMessage = proto::decode_message(message)?;
+                LiveEvent::Chat {
+                    msg_id: msg_id(&msg.common),
+                    user: event_user(&msg.user),
+                    content: msg.content,
+                }
+            }
+            "WebcastGiftMessage" => {
+                let msg: douyin::GiftMessage = proto::decode_message(message)?;
+                let gift = msg.gift.unwrap_or_default();
+                LiveEvent::Gift {
+                    msg_id: msg_id(&msg.common),
+                    user: event_user(&msg.user),
+                    gift_id: if gift.id != 0 { gift.id } else { msg.gift_id },
+                    gift_name: gift.name,
+                    diamond_count: gift.diamond_count,
+                    repeat_count: msg.repeat_count,
+                    combo_count: msg.combo_count,
+                    group_count: msg.group_count,
+                    repeat_end: msg.repeat_end == 1,
+                    combo: gift.combo,
+                }
+            }
+            "WebcastLikeMessage" => {
+                let msg: douyin::LikeMessage = proto::decode_message(message)?;
+                LiveEvent::Like {
+                    msg_id: msg_id(&msg.common),
+                    user: event_user(&msg.user),
+                    count: msg.count,
+                    total: msg.total,
+                }
+            }
+            "WebcastMemberMessage" => {
+                let msg: douyin::MemberMessage = proto::decode_message(message)?;
+                LiveEvent::Member {
+                    msg_id: msg_id(&msg.common),
+                    user: event_user(&msg.user),
+                    member_count: msg.member_count,
+                    action: msg.action,
+                }
+            }
+            "WebcastSocialMessage" => {
+                let msg: douyin::SocialMessage = proto::decode_message(message)?;
+                LiveEvent::Follow {
+                    msg_id: msg_id(&msg.common),
+                    user: event_user(&msg.user),
+                    action: msg.action,
+                    follow_count: msg.follow_count,
+                }
+            }
+            "WebcastRoomUserSeqMessage" => {
+                let msg: douyin::RoomUserSeqMessage = proto::decode_message(message)?;
+                LiveEvent::RoomUserSeq {
+                    msg_id: msg_id(&msg.common),
+                    total: msg.total,
+                    total_user: msg.total_user,
+                    online_user_for_anchor: msg.online_user_for_anchor,
+                    total_pv_for_anchor: msg.total_pv_for_anchor,
+                }
+            }
+            "WebcastUpdateFanTicketMessage" => {
+                let msg: douyin::UpdateFanTicketMessage = proto::decode_message(message)?;
+                LiveEvent::FanTicket {
+                    msg_id: msg_id(&msg.common),
+                    count: msg.room_fan_ticket_count,
+                    count_text: msg.room_fan_ticket_count_text,
+                }
+            }
+            "WebcastCommonTextMessage" => {
+                let msg: douyin::CommonTextMessage = proto::decode_message(message)?;
+                LiveEvent::CommonText {
+                    msg_id: msg_id(&msg.common),
+                    user: msg.user.as_ref().map(EventUser::from),
+                    scene: msg.scene,
+                    describe: msg.common.map(|c| c.describe).unwrap_or_default(),
+                }
+            }
+            "WebcastProductChangeMessage" => {
+                let msg: douyin::ProductChangeMessage = proto::decode_message(message)?;
+                LiveEvent::ProductChange {
+                    msg_id: msg_id(&msg.common),
+                    update_timestamp: msg.update_timestamp,
+                    update_toast: msg.update_toast,
+                    total: msg.total,
+                }
+            }
+            "WebcastMatchAgainstScoreMessage" => {
+                let msg: douyin::MatchAgainstScoreMessage = proto::decode_message(message)?;
+                let against = msg.against.unwrap_or_default();
+                LiveEvent::MatchAgainstScore {
+                    msg_id: msg_id(&msg.common),
+                    left_name: against.left_name,
+                    left_goal: against.left_goal,
+                    right_name: against.right_name,
+                    right_goal: against.right_goal,
+                    match_status: msg.match_status,
+                }
+            }
+            "WebcastRoomStatsMessage" => {
+                let msg: douyin::RoomStatsMessage = proto::decode_message(message)?;
+                LiveEvent::RoomStats {
+                    msg_id: msg_id(&msg.common),
+                    display_short: msg.display_short,
+                    display_long: msg.display_long,
+                    display_value: msg.display_value,
+                    total: msg.total,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
+
+fn serialize_base64<S: serde::Serializer>(raw: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&STANDARD.encode(raw))
+}
+
+// Common 里的 msgId，缺失时为空字符串
+fn msg_id(common: &Option<douyin::Common>) -> String {
+    common
+        .as_ref()
+        .map(|c| c.msg_id.to_string())
+        .unwrap_or_default()
+}
+
+fn event_user(user: &Option<douyin::User>) -> EventUser {
+    user.as_ref().map(EventUser::from).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn common(msg_id: u64) -> Option<douyin::Common> {
+        Some(douyin::Common {
+            msg_id,
+            describe: "小明 来了".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn user() -> Option<douyin::User> {
+        Some(douyin::User {
+            id: 42,
+            nick_name: "小明".to_string(),
+            ..Default::default()
+        })
+    }
+
+    // 按 method 编码成 Response 里的一条消息，解析后序列化成 JSON 方便比较
+    fn event(method: &str, payload: impl prost::Message) -> Value {
+        let message = proto::Message {
+            method: method.to_string(),
+            payload: payload.encode_to_vec(),
+            msg_id: 1,
+            ..Default::default()
+        };
+        serde_json::to_value(LiveEvent::from_message(&message)).unwrap()
+    }
+
+    #[test]
+    fn chat_gift_like() {
+        let chat = douyin::ChatMessage {
+            common: common(11),
+            user: user(),
+            content: "主播晚上好".to_string(),
+        };
+        let chat = event("WebcastChatMessage", chat);
+        assert_eq!(chat["type"], "chat");
+        assert_eq!(chat["msg_id"], "11");
+        assert_eq!(chat["user"]["id"], "42");
+        assert_eq!(chat["user"]["nick_name"], "小明");
+        assert_eq!(chat["content"], "主播晚上好");
+
+        let gift = douyin::GiftMessage {
+            common: common(12),
+            user: user(),
+            gift_id: 1,
+            repeat_count: 3,
+            repeat_end: 1,
+            gift: Some(douyin::GiftStruct {
+                id: 463,
+                name: "小心心".to_string(),
+                diamond_count: 1,
+                combo: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let gift = event("WebcastGiftMessage", gift);
+        assert_eq!(gift["type"], "gift");
+        assert_eq!(gift["gift_id"], 463);
+        assert_eq!(gift["gift_name"], "小心心");
+        assert_eq!(gift["repeat_count"], 3);
+        assert_eq!(gift["repeat_end"], true);
+        assert_eq!(gift["combo"], true);
+
+        let like = douyin::LikeMessage {
+            common: common(13),
+            user: user(),
+            count: 5,
+            total: 1000,
+            ..Default::default()
+        };
+        let like = event("WebcastLikeMessage", like);
+        assert_eq!(like["type"], "like");
+        assert_eq!((&like["count"], &like["total"]), (&json!(5), &json!(1000)));
+    }
+
+    #[test]
+    fn member_follow_common_text() {
+        let member = douyin::MemberMessage {
+            common: common(21),
+            user: user(),
+            member_count: 128,
+            action: 1,
+            ..Default::default()
+        };
+        let member = event("WebcastMemberMessage", member);
+        assert_eq!(member["type"], "member");
+        assert_eq!(member["member_count"], 128);
+
+        let follow = douyin::SocialMessage {
+            common: common(22),
+            user: user(),
+            action: 1,
+            follow_count: 3000,
+            ..Default::default()
+        };
+        let follow = event("WebcastSocialMessage", follow);
+        assert_eq!(follow["type"], "follow");
+        assert_eq!(follow["follow_count"], 3000);
+
+        let text = douyin::CommonTextMessage {
+            common: common(23),
+            user: None,
+            scene: "enter".to_string(),
+        };
+        let text = event("WebcastCommonTextMessage", text);
+        assert_eq!(text["type"], "common_text");
+        assert_eq!(text["user"], Value::Null);
+        assert_eq!(text["describe"], "小明 来了");
+    }
+
+    #[test]
+    fn room_stats_messages() {
+        let seq = douyin::RoomUserSeqMessage {
+            common: common(31),
+            total: 500,
+            total_user: 2000,
+            online_user_for_anchor: "500".to_string(),
+            ..Default::default()
+        };
+        let seq = event("WebcastRoomUserSeqMessage", seq);
+        assert_eq!(seq["type"], "room_user_seq");
+        assert_eq!(seq["total_user"], 2000);
+
+        let ticket = douyin::UpdateFanTicketMessage {
+            common: common(32),
+            room_fan_ticket_count: 99,
+            room_fan_ticket_count_text: "99".to_string(),
+            ..Default::default()
+        };
+        let ticket = event("WebcastUpdateFanTicketMessage", ticket);
+        assert_eq!(ticket["type"], "fan_ticket");
+        assert_eq!(ticket["count"], 99);
+
+        let product = douyin::ProductChangeMessage {
+            common: common(33),
+            update_toast: "上新了".to_string(),
+            total: 4,
+            ..Default::default()
+        };
+        let product = event("WebcastProductChangeMessage", product);
+        assert_eq!(product["type"], "product_change");
+        assert_eq!(product["update_toast"], "上新了");
+
+        let against = douyin::MatchAgainstScoreMessage {
+            common: common(34),
+            against: Some(douyin::Against {
+                left_name: "红队".to_string(),
+                left_goal: "1".to_string(),
+                right_name: "蓝队".to_string(),
+                right_goal: "2".to_string(),
+                ..Default::default()
+            }),
+            match_status: 2,
+            ..Default::default()
+        };
+        let against = event("WebcastMatchAgainstScoreMessage", against);
+        assert_eq!(against["type"], "match_against_score");
+        assert_eq!(against["right_goal"], "2");
+
+        let stats = douyin::RoomStatsMessage {
+            common: common(35),
+            display_short: "1万".to_string(),
+            display_value: 10000,
+            ..Default::default()
+        };
+        let stats = event("WebcastRoomStatsMessage", stats);
+        assert_eq!(stats["type"], "room_stats");
+        assert_eq!(stats["msg_id"], "35");
+        assert_eq!(stats["display_value"], 10000);
+    }
+
+    #[test]
+    fn unknown_fallback() {
+        // 没有解析的 method 和解码失败的消息都保留原始 payload，序列化为 base64
+        let message = proto::Message {
+            method: "WebcastInRoomBannerMessage".to_string(),
+            payload: vec![0x08, 0x96, 0x01],
+            ..Default::default()
+        };
+        let unknown = LiveEvent::from_message(&message);
+        assert_eq!(unknown.kind(), "unknown");
+        assert_eq!(unknown.msg_id(), None);
+        assert_eq!(
+            serde_json::to_value(&unknown).unwrap(),
+            json!({"type": "unknown", "method": "WebcastInRoomBannerMessage", "raw": "CJYB"})
+        );
+
+        let broken = proto::Message {
+            method: "WebcastChatMessage".to_string(),
+            payload: vec![0xff, 0xff],
+            ..Default::default()
+        };
+        let broken = serde_json::to_value(LiveEvent::from_message(&broken)).unwrap();
+        assert_eq!(broken["type"], "unknown");
+        assert_eq!(broken["raw"], "//8=");
+    }
+}
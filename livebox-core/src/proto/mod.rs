@@ -0,0 +1,115 @@
+use flate2::read::GzDecoder;
+use prost::Message as _;
+use std::io::Read;
+
+// 由 build.rs 根据 src/proto/dy.proto 自动生成，和前端的 dy.js 使用同一份定义
+#[allow(clippy::all)]
+pub mod douyin {
+    include!(concat!(env!("OUT_DIR"), "/douyin.rs"));
+}
+
+pub use douyin::{Message, PushFrame, Response};
+
+/// 解码 WebSocket 收到的一帧二进制数据
+pub fn decode_push_frame(
+    data: &[u8],
+) -> Result<PushFrame, Box<dyn std::error::Error + Send + Sync>> {
+    let frame = PushFrame::decode(data)?;
+    Ok(frame)
+}
+
+/// 解压 PushFrame 的 payload，并解码为 Response
+pub fn decode_response(
+    frame: &PushFrame,
+) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+    // 正常情况下 payload 都是 gzip 压缩过的，个别帧没有压缩就直接解码
+    let response = if frame.payload_encoding.is_empty() || frame.payload_encoding == "gzip" {
+        let mut buf = Vec::new();
+        GzDecoder::new(frame.payload.as_slice()).read_to_end(&mut buf)?;
+        Response::decode(buf.as_slice())?
+    } else {
+        Response::decode(frame.payload.as_slice())?
+    };
+    Ok(response)
+}
+
+/// 完整的解码流程：PushFrame -> gzip 解压 -> Response
+pub fn decode_frame(
+    data: &[u8],
+) -> Result<(PushFrame, Response), Box<dyn std::error::Error + Send + Sync>> {
+    let frame = decode_push_frame(data)?;
+    let response = decode_response(&frame)?;
+    Ok((frame, response))
+}
+
+/// 按具体类型解码 messagesList 里的单条消息，例如 ChatMessage、GiftMessage
+pub fn decode_message<T: prost::Message + Default>(
+    message: &Message,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    let decoded = T::decode(message.payload.as_slice())?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::douyin::{ChatMessage, GiftMessage, MemberMessage};
+    use super::*;
+
+    // 一帧 gzip 压缩的推送：一条弹幕、一条礼物、一条进场
+    const FRAME: &[u8] = include_bytes!("../../../analysis/push_frame.bin");
+
+    #[test]
+    fn decode_captured_frame() {
+        let (frame, response) = decode_frame(FRAME).unwrap();
+        assert_eq!(frame.payload_type, "msg");
+        assert_eq!(frame.payload_encoding, "gzip");
+        assert!(response.need_ack);
+        assert_eq!(response.heartbeat_duration, 10000);
+        assert!(response.internal_ext.contains("wss_push_room_id"));
+
+        let methods: Vec<&str> = response
+            .messages_list
+            .iter()
+            .map(|m| m.method.as_str())
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                "WebcastChatMessage",
+                "WebcastGiftMessage",
+                "WebcastMemberMessage"
+            ]
+        );
+
+        let messages = &response.messages_list;
+        let chat: ChatMessage = decode_message(&messages[0]).unwrap();
+        assert_eq!(chat.content, "主播晚上好");
+        assert_eq!(chat.user.unwrap().nick_name, "小明");
+        assert_eq!(chat.common.unwrap().msg_id, 7401);
+
+        let gift: GiftMessage = decode_message(&messages[1]).unwrap();
+        assert_eq!(gift.gift_id, 463);
+        assert_eq!(gift.repeat_count, 1);
+        let info = gift.gift.unwrap();
+        assert_eq!(info.name, "小心心");
+        assert_eq!(info.diamond_count, 1);
+
+        let member: MemberMessage = decode_message(&messages[2]).unwrap();
+        assert_eq!(member.user.unwrap().nick_name, "路人甲");
+        assert_eq!(member.member_count, 128);
+    }
+
+    #[test]
+    fn uncompressed_payload() {
+        let mut frame = decode_push_frame(FRAME).unwrap();
+        let response = decode_response(&frame).unwrap();
+        frame.payload_encoding = "none".to_string();
+        frame.payload = prost::Message::encode_to_vec(&response);
+        assert_eq!(decode_response(&frame).unwrap(), response);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(decode_frame(b"not a push frame").is_err());
+    }
+}
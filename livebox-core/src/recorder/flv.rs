@@ -0,0 +1,305 @@
+use std::io;
+use tokio::sync::oneshot;
+
+use super::{or_stop, Outcome, Segments, READ_TIMEOUT};
+
+const TAG_AUDIO: u8 = 8;
+const TAG_VIDEO: u8 = 9;
+const TAG_SCRIPT: u8 = 18;
+// tag 头 11 字节，后面跟着 4 字节的 PreviousTagSize
+const TAG_HEADER_LEN: usize = 11;
+
+// 下载一次 FLV 流，直到断开、结束或者收到停止信号
+pub(super) async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    segments: &mut Segments,
+    stop: &mut oneshot::Receiver<()>,
+) -> Result<Outcome, String> {
+    let Some(response) = or_stop(stop, client.get(url).send()).await else {
+        return Ok(Outcome::Stopped);
+    };
+    let mut response = response.map_err(|e| format!("连接直播流失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("直播流返回 {}", response.status()));
+    }
+
+    let mut splitter = FlvSplitter::default();
+    loop {
+        let read = tokio::time::timeout(READ_TIMEOUT, response.chunk());
+        let Some(read) = or_stop(stop, read).await else {
+            return Ok(Outcome::Stopped);
+        };
+        let chunk = match read {
+            Err(_) => return Err(format!("{} 秒没有收到数据", READ_TIMEOUT.as_secs())),
+            Ok(Err(e)) => return Err(format!("读取直播流失败: {}", e)),
+            Ok(Ok(None)) => return Ok(Outcome::Ended),
+            Ok(Ok(Some(chunk))) => chunk,
+        };
+        splitter
+            .push(&chunk, segments)
+            .map_err(|e| format!("写入录制文件失败: {}", e))?;
+    }
+}
+
+// 按 tag 切分 FLV 流，只在视频关键帧处换新文件
+//
+// 每个分段都重新写 FLV 头、metadata 和音视频的 sequence header，时间戳从 0 开始，单独也能播放
+#[derive(Default)]
+struct FlvSplitter {
+    buf: Vec<u8>,
+    header_read: bool,
+    flags: u8,
+    metadata: Option<Vec<u8>>,
+    video_header: Option<Vec<u8>>,
+    audio_header: Option<Vec<u8>>,
+    has_video: bool,
+    // 当前分段第一个 tag 的原始时间戳，None 表示这次连接还没有开始写
+    base: Option<u32>,
+}
+
+impl FlvSplitter {
+    fn push(&mut self, chunk: &[u8], segments: &mut Segments) -> io::Result<()> {
+        self.buf.extend_from_slice(chunk);
+        let mut pos = 0;
+        if !self.header_read {
+            // FLV 头 9 字节 + 第一个 PreviousTagSize
+            if self.buf.len() < 13 {
+                return Ok(());
+            }
+            if &self.buf[..3] != b"FLV" {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "不是 FLV 数据"));
+            }
+            let offset =
+                u32::from_be_bytes([self.buf[5], self.buf[6], self.buf[7], self.buf[8]]) as usize;
+            if self.buf.len() < offset + 4 {
+                return Ok(());
+            }
+            self.flags = self.buf[4];
+            self.header_read = true;
+            pos = offset + 4;
+        }
+
+        while self.buf.len() - pos >= TAG_HEADER_LEN {
+            let size = u24(&self.buf[pos + 1..pos + 4]) as usize;
+            let end = pos + TAG_HEADER_LEN + size + 4;
+            if self.buf.len() < end {
+                break;
+            }
+            let tag = self.buf[pos..end].to_vec();
+            pos = end;
+            self.handle_tag(tag, segments)?;
+        }
+        self.buf.drain(..pos);
+        Ok(())
+    }
+
+    fn handle_tag(&mut self, tag: Vec<u8>, segments: &mut Segments) -> io::Result<()> {
+        let kind = tag[0] & 0x1f;
+        let body = &tag[TAG_HEADER_LEN..tag.len() - 4];
+        let keyframe = match kind {
+            TAG_SCRIPT => {
+                self.metadata = Some(tag);
+                return Ok(());
+            }
+            TAG_VIDEO if is_video_sequence_header(body) => {
+                self.has_video = true;
+                self.video_header = Some(tag.clone());
+                return self.write_config(tag, segments);
+            }
+            TAG_AUDIO if is_audio_sequence_header(body) => {
+                self.audio_header = Some(tag.clone());
+                return self.write_config(tag, segments);
+            }
+            TAG_VIDEO => {
+                self.has_video = true;
+                is_keyframe(body)
+            }
+            TAG_AUDIO => false,
+            _ => return Ok(()),
+        };
+        // 纯音频的流在任意位置都可以切
+        let boundary = keyframe || (kind == TAG_AUDIO && !self.has_video);
+        let timestamp = read_timestamp(&tag);
+        if boundary && (self.base.is_none() || segments.is_full()) {
+            self.open_segment(timestamp, segments)?;
+        }
+        // 第一个关键帧之前的数据播放不了，丢掉
+        let Some(base) = self.base else {
+            return Ok(());
+        };
+        segments.write(&rebase(tag, base))
+    }
+
+    // 流中间更新的 sequence header 也要写进当前分段
+    fn write_config(&mut self, tag: Vec<u8>, segments: &mut Segments) -> io::Result<()> {
+        match self.base {
+            Some(base) => segments.write(&rebase(tag, base)),
+            None => Ok(()),
+        }
+    }
+
+    fn open_segment(&mut self, timestamp: u32, segments: &mut Segments) -> io::Result<()> {
+        segments.open_next()?;
+        let mut header = b"FLV\x01".to_vec();
+        header.push(self.flags);
+        header.extend_from_slice(&9u32.to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        segments.write(&header)?;
+        for tag in [&self.metadata, &self.video_header, &self.audio_header]
+            .into_iter()
+            .flatten()
+        {
+            let mut tag = tag.clone();
+            set_timestamp(&mut tag, 0);
+            segments.write(&tag)?;
+        }
+        self.base = Some(timestamp);
+        Ok(())
+    }
+}
+
+fn u24(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+}
+
+// 时间戳是 3 字节加 1 字节扩展的高 8 位
+fn read_timestamp(tag: &[u8]) -> u32 {
+    u32::from_be_bytes([tag[7], tag[4], tag[5], tag[6]])
+}
+
+// 把时间戳改成相对分段开始的值，早于 base 的记为 0
+fn rebase(mut tag: Vec<u8>, base: u32) -> Vec<u8> {
+    let timestamp = read_timestamp(&tag).saturating_sub(base);
+    set_timestamp(&mut tag, timestamp);
+    tag
+}
+
+fn set_timestamp(tag: &mut [u8], timestamp: u32) {
+    let bytes = timestamp.to_be_bytes();
+    tag[4..7].copy_from_slice(&bytes[1..]);
+    tag[7] = bytes[0];
+}
+
+// 视频 tag 第一个字节高 4 位是帧类型（1 为关键帧），enhanced RTMP 的最高位是 1
+fn is_keyframe(body: &[u8]) -> bool {
+    body.first().is_some_and(|b| (b >> 4) & 0x07 == 1)
+}
+
+// AVC / HEVC 的 sequence header，enhanced RTMP 里是 PacketTypeSequenceStart
+fn is_video_sequence_header(body: &[u8]) -> bool {
+    match body {
+        [b, ..] if b & 0x80 != 0 => b & 0x0f == 0,
+        [b, 0, ..] => matches!(b & 0x0f, 7 | 12),
+        _ => false,
+    }
+}
+
+// AAC 的 AudioSpecificConfig
+fn is_audio_sequence_header(body: &[u8]) -> bool {
+    matches!(body, [b, 0, ..] if b >> 4 == 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"FLV\x01\x05\x00\x00\x00\x09\x00\x00\x00\x00";
+
+    fn tag(kind: u8, timestamp: u32, body: &[u8]) -> Vec<u8> {
+        let size = (body.len() as u32).to_be_bytes();
+        let mut tag = vec![kind, size[1], size[2], size[3], 0, 0, 0, 0, 0, 0, 0];
+        set_timestamp(&mut tag, timestamp);
+        tag.extend_from_slice(body);
+        tag.extend_from_slice(&(TAG_HEADER_LEN as u32 + body.len() as u32).to_be_bytes());
+        tag
+    }
+
+    // 分段文件里每个 tag 的类型、时间戳和 body 第一个字节
+    fn tags(file: &[u8]) -> Vec<(u8, u32, u8)> {
+        assert_eq!(&file[..13], HEADER);
+        let mut tags = Vec::new();
+        let mut pos = 13;
+        while pos < file.len() {
+            let size = u24(&file[pos + 1..pos + 4]) as usize;
+            let end = pos + TAG_HEADER_LEN + size + 4;
+            let tag = &file[pos..end];
+            tags.push((tag[0], read_timestamp(tag), tag[TAG_HEADER_LEN]));
+            pos = end;
+        }
+        tags
+    }
+
+    fn stream() -> Vec<u8> {
+        let mut data = HEADER.to_vec();
+        for tag in [
+            tag(TAG_SCRIPT, 0, b"\x02meta"),
+            tag(TAG_VIDEO, 0, &[0x17, 0, 0, 0, 0]),
+            tag(TAG_AUDIO, 0, &[0xaf, 0, 0x12]),
+            // 第一个关键帧之前的帧会被丢掉
+            tag(TAG_VIDEO, 900, &[0x27, 1]),
+            tag(TAG_VIDEO, 1000, &[0x17, 1]),
+            tag(TAG_AUDIO, 1020, &[0xaf, 1]),
+            tag(TAG_VIDEO, 1040, &[0x27, 1]),
+            tag(TAG_VIDEO, 2000, &[0x17, 1]),
+            tag(TAG_AUDIO, 2020, &[0xaf, 1]),
+        ] {
+            data.extend(tag);
+        }
+        data
+    }
+
+    #[test]
+    fn splits_at_keyframes() {
+        let mut segments = Segments::for_test("flv-split", Some(1));
+        let mut splitter = FlvSplitter::default();
+        splitter.push(&stream(), &mut segments).unwrap();
+        let files = segments.finish_test();
+
+        // 每个分段都带上 metadata 和 sequence header，时间戳从 0 开始
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            tags(&files[0]),
+            [
+                (TAG_SCRIPT, 0, 0x02),
+                (TAG_VIDEO, 0, 0x17),
+                (TAG_AUDIO, 0, 0xaf),
+                (TAG_VIDEO, 0, 0x17),
+                (TAG_AUDIO, 20, 0xaf),
+                (TAG_VIDEO, 40, 0x27),
+            ]
+        );
+        assert_eq!(
+            tags(&files[1]),
+            [
+                (TAG_SCRIPT, 0, 0x02),
+                (TAG_VIDEO, 0, 0x17),
+                (TAG_AUDIO, 0, 0xaf),
+                (TAG_VIDEO, 0, 0x17),
+                (TAG_AUDIO, 20, 0xaf),
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let mut segments = Segments::for_test("flv-bytes", None);
+        let mut splitter = FlvSplitter::default();
+        for byte in stream() {
+            splitter.push(&[byte], &mut segments).unwrap();
+        }
+        let files = segments.finish_test();
+        assert_eq!(files.len(), 1);
+        let timestamps: Vec<u32> = tags(&files[0]).iter().map(|t| t.1).collect();
+        assert_eq!(timestamps, [0, 0, 0, 0, 20, 40, 1000, 1020]);
+    }
+
+    #[test]
+    fn rejects_non_flv() {
+        let mut segments = Segments::for_test("flv-invalid", None);
+        let mut splitter = FlvSplitter::default();
+        let result = splitter.push(b"#EXTM3U\n#EXT-X-VERSION:3\n", &mut segments);
+        assert!(result.is_err());
+        assert!(segments.finish_test().is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use reqwest::Url;
+use std::time::Instant;
+use tokio::sync::oneshot;
+use tokio::time::Duration;
+
+use super::{or_stop, Outcome, Segments, READ_TIMEOUT};
+
+// 列表刷新间隔的上下限
+const MIN_POLL: Duration = Duration::from_secs(1);
+const MAX_POLL: Duration = Duration::from_secs(5);
+// 多码率列表最多跳转几次，防止列表指向自己时一直循环
+const MAX_VARIANT_HOPS: usize = 3;
+
+// 轮询 m3u8 列表，把新出现的 TS 分片依次追加到录制文件，只在分片边界换新文件
+//
+// last_sequence 是这次录制写入的最后一个分片序号，由调用方在重连之间保留，重连后不会重复写入
+pub(super) async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    last_sequence: &mut Option<u64>,
+    segments: &mut Segments,
+    stop: &mut oneshot::Receiver<()>,
+) -> Result<Outcome, String> {
+    let mut playlist_url = Url::parse(url).map_err(|e| format!("拉流地址无效: {}", e))?;
+    let mut hops = 0;
+    let mut first = true;
+    let mut last_new = Instant::now();
+    loop {
+        let Some(text) = or_stop(stop, fetch(client, &playlist_url)).await else {
+            return Ok(Outcome::Stopped);
+        };
+        let playlist = Playlist::parse(&String::from_utf8_lossy(&text?));
+        // 多码率的列表，取第一个子列表
+        if let Some(variant) = &playlist.variant {
+            hops += 1;
+            if hops > MAX_VARIANT_HOPS {
+                return Err(format!("多码率列表跳转超过 {} 次", MAX_VARIANT_HOPS));
+            }
+            playlist_url = playlist_url
+                .join(variant)
+                .map_err(|e| format!("子列表地址无效: {}", e))?;
+            continue;
+        }
+        // 直播中的列表序号只会往前走，重连后最新的分片反而更早，说明推流重新开始了，序号从头算
+        if first {
+            first = false;
+            let newest = playlist.sequenced().last().map(|(sequence, _)| sequence);
+            if let (Some(newest), Some(last)) = (newest, *last_sequence) {
+                if newest < last {
+                    eprintln!("⚠️ [录制] HLS 分片序号重新开始了");
+                    *last_sequence = None;
+                }
+            }
+        }
+
+        for (sequence, uri) in playlist.sequenced() {
+            if last_sequence.is_some_and(|last| sequence <= last) {
+                continue;
+            }
+            if let Some(last) = last_sequence.filter(|last| sequence > last + 1) {
+                eprintln!("⚠️ [录制] 跳过了 {} 个 HLS 分片", sequence - last - 1);
+            }
+            let media_url = playlist_url
+                .join(uri)
+                .map_err(|e| format!("分片地址无效: {}", e))?;
+            let Some(data) = or_stop(stop, fetch(client, &media_url)).await else {
+                return Ok(Outcome::Stopped);
+            };
+            let data = data?;
+            if !segments.is_open() || segments.is_full() {
+                segments
+                    .open_next()
+                    .map_err(|e| format!("创建录制文件失败: {}", e))?;
+            }
+            segments
+                .write(&data)
+                .map_err(|e| format!("写入录制文件失败: {}", e))?;
+            *last_sequence = Some(sequence);
+            last_new = Instant::now();
+        }
+
+        if playlist.ended {
+            return Ok(Outcome::Ended);
+        }
+        if last_new.elapsed() > READ_TIMEOUT * 2 {
+            return Err(format!("{} 秒没有新的分片", (READ_TIMEOUT * 2).as_secs()));
+        }
+        let poll =
+            Duration::from_secs_f64(playlist.target_duration / 2.0).clamp(MIN_POLL, MAX_POLL);
+        if or_stop(stop, tokio::time::sleep(poll)).await.is_none() {
+            return Ok(Outcome::Stopped);
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &Url) -> Result<Vec<u8>, String> {
+    let request = async {
+        let response = client.get(url.clone()).send().await?.error_for_status()?;
+        response.bytes().await
+    };
+    match tokio::time::timeout(READ_TIMEOUT, request).await {
+        Ok(Ok(bytes)) => Ok(bytes.to_vec()),
+        Ok(Err(e)) => Err(format!("请求 {} 失败: {}", url.path(), e)),
+        Err(_) => Err(format!("请求 {} 超时", url.path())),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Playlist {
+    target_duration: f64,
+    media_sequence: u64,
+    segments: Vec<String>,
+    // 多码率列表里的第一个子列表
+    variant: Option<String>,
+    ended: bool,
+}
+
+impl Playlist {
+    fn parse(text: &str) -> Self {
+        let mut playlist = Playlist::default();
+        let mut stream_inf = false;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+                playlist.target_duration = value.trim().parse().unwrap_or(0.0);
+            } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+                playlist.media_sequence = value.trim().parse().unwrap_or(0);
+            } else if line.starts_with("#EXT-X-STREAM-INF") {
+                stream_inf = true;
+            } else if line == "#EXT-X-ENDLIST" {
+                playlist.ended = true;
+            } else if line.starts_with('#') {
+                continue;
+            } else if stream_inf {
+                playlist.variant.get_or_insert_with(|| line.to_string());
+                stream_inf = false;
+            } else {
+                playlist.segments.push(line.to_string());
+            }
+        }
+        playlist
+    }
+
+    // 每个分片和它的序号
+    fn sequenced(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(i, uri)| (self.media_sequence + i as u64, uri.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_media_playlist() {
+        let playlist = Playlist::parse(
+            "#EXTM3U
+            #EXT-X-VERSION:3
+            #EXT-X-TARGETDURATION:4
+            #EXT-X-MEDIA-SEQUENCE:120
+            #EXTINF:4.000,
+            stream-120.ts
+            #EXTINF:4.000,
+            stream-121.ts?token=1
+            #EXTINF:4.000,
+
+            stream-122.ts
+            ",
+        );
+        assert_eq!(playlist.target_duration, 4.0);
+        assert!(playlist.variant.is_none());
+        assert!(!playlist.ended);
+        let sequenced: Vec<_> = playlist.sequenced().collect();
+        assert_eq!(
+            sequenced,
+            [
+                (120, "stream-120.ts"),
+                (121, "stream-121.ts?token=1"),
+                (122, "stream-122.ts")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_variant_playlist() {
+        let playlist = Playlist::parse(
+            "#EXTM3U
+            #EXT-X-STREAM-INF:BANDWIDTH=4000000,RESOLUTION=1920x1080
+            origin/index.m3u8
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000,RESOLUTION=854x480
+            sd/index.m3u8",
+        );
+        assert_eq!(playlist.variant.as_deref(), Some("origin/index.m3u8"));
+        assert!(playlist.segments.is_empty());
+    }
+
+    #[test]
+    fn parse_ended_playlist() {
+        let playlist = Playlist::parse(
+            "#EXTM3U
+            #EXT-X-TARGETDURATION:2
+            #EXTINF:2.0,
+            last.ts
+            #EXT-X-ENDLIST",
+        );
+        assert!(playlist.ended);
+        // 没有 MEDIA-SEQUENCE 时从 0 开始
+        assert_eq!(playlist.sequenced().collect::<Vec<_>>(), [(0, "last.ts")]);
+    }
+}
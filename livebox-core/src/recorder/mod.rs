@@ -0,0 +1,554 @@
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue, REFERER};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Duration;
+
+mod flv;
+mod hls;
+
+use crate::model::LiveEvent;
+use crate::runner::DouYinReq;
+use crate::stream::{Quality, StreamSources};
+use crate::utils::paths::livebox_dir;
+use crate::utils::time::now_millis;
+use crate::webcast::USER_AGENT;
+
+// 重连等待时间从 1 秒开始翻倍，最多 30 秒
+const RECONNECT_BASE: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+// 连续失败这么多次后停止录制
+const MAX_RECONNECT_ATTEMPTS: u32 = 8;
+// 连续失败这么多次后重新获取拉流地址，地址可能已经过期
+const REFRESH_AFTER_ATTEMPTS: u32 = 2;
+// 超过这个时间没有收到数据就当作断开
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 默认录制目录 ~/.livebox/recordings
+pub fn default_record_dir() -> PathBuf {
+    livebox_dir().join("recordings")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamFormat {
+    Flv,
+    Hls,
+}
+
+impl StreamFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            StreamFormat::Flv => "flv",
+            // HLS 的 TS 分片可以直接拼接
+            StreamFormat::Hls => "ts",
+        }
+    }
+}
+
+impl FromStr for StreamFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "flv" => Ok(StreamFormat::Flv),
+            "hls" | "m3u8" => Ok(StreamFormat::Hls),
+            _ => Err(format!("不支持的格式: {}，可选 flv、hls", s)),
+        }
+    }
+}
+
+// 录制设置
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub dir: PathBuf,
+    // 没有这个清晰度时依次取更低的
+    pub quality: Quality,
+    // 没有这种格式的地址时用另一种
+    pub format: StreamFormat,
+    // 分段的大小和时长，达到任意一个就换新文件，None 表示不限制
+    pub segment_bytes: Option<u64>,
+    pub segment_secs: Option<u64>,
+    // 写入旁路文件的事件类型（chat、gift 等），None 表示全部写入
+    pub sidecar_types: Option<Vec<String>>,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        RecordOptions {
+            dir: default_record_dir(),
+            quality: Quality::Origin,
+            format: StreamFormat::Flv,
+            segment_bytes: None,
+            segment_secs: Some(30 * 60),
+            sidecar_types: None,
+        }
+    }
+}
+
+// 录制状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum RecordState {
+    Connecting,
+    // 正在写入第 segment 个分段（从 1 开始）
+    Recording {
+        segment: usize,
+        path: String,
+    },
+    Reconnecting {
+        attempt: u32,
+        delay_ms: u64,
+        reason: String,
+    },
+    Stopped {
+        reason: String,
+    },
+}
+
+// 录制的信息，给界面显示
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordInfo {
+    pub room_id: String,
+    pub quality: Quality,
+    pub format: StreamFormat,
+    pub stream_url: String,
+    // 写入第一个分段的时间，旁路文件里的 offset_ms 从这里开始算
+    pub started_at: u64,
+    pub bytes: u64,
+    pub segments: Vec<String>,
+    pub sidecar_path: String,
+    pub state: RecordState,
+}
+
+// 旁路文件里的一行
+#[derive(Serialize)]
+struct SidecarLine<'a> {
+    // 相对录制开始的毫秒数
+    offset_ms: u64,
+    // 所在的分段和相对分段开始的毫秒数
+    segment: usize,
+    segment_offset_ms: u64,
+    received_at: u64,
+    event: &'a LiveEvent,
+}
+
+struct Shared {
+    info: RecordInfo,
+    sidecar: BufWriter<File>,
+    sidecar_types: Option<Vec<String>>,
+    segment_started_at: u64,
+}
+
+// 把直播流录制到文件，同时把弹幕等事件写到旁路的 JSONL 文件里，方便和视频对齐
+pub struct Recorder {
+    shared: Arc<Mutex<Shared>>,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl Recorder {
+    /// 开始录制，状态变化通过 tx 发出；room_url 用来在拉流地址失效时重新获取
+    pub fn start(
+        room_id: &str,
+        sources: &StreamSources,
+        options: RecordOptions,
+        room_url: Option<String>,
+        tx: mpsc::UnboundedSender<RecordState>,
+    ) -> io::Result<Self> {
+        let (url, quality, format) = pick_stream(sources, options.quality, options.format)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "没有可以录制的拉流地址"))?;
+        fs::create_dir_all(&options.dir)?;
+        let prefix = format!("{}-{}", room_id, now_millis());
+        let sidecar_path = options.dir.join(format!("{}.jsonl", prefix));
+        let sidecar = BufWriter::new(File::create(&sidecar_path)?);
+        eprintln!(
+            "🎬 [录制] 开始录制 {} ({:?} {:?})",
+            room_id, quality, format
+        );
+
+        let shared = Arc::new(Mutex::new(Shared {
+            info: RecordInfo {
+                room_id: room_id.to_string(),
+                quality,
+                format,
+                stream_url: url.clone(),
+                started_at: 0,
+                bytes: 0,
+                segments: Vec::new(),
+                sidecar_path: sidecar_path.to_string_lossy().to_string(),
+                state: RecordState::Connecting,
+            },
+            sidecar,
+            sidecar_types: options.sidecar_types,
+            segment_started_at: 0,
+        }));
+        let segments = Segments {
+            shared: shared.clone(),
+            tx,
+            dir: options.dir,
+            prefix,
+            extension: format.extension(),
+            max_bytes: options.segment_bytes.filter(|b| *b > 0),
+            max_duration: options
+                .segment_secs
+                .filter(|s| *s > 0)
+                .map(Duration::from_secs),
+            file: None,
+            bytes: 0,
+            opened_at: Instant::now(),
+        };
+        segments.set_state(RecordState::Connecting);
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let task = Task {
+            room_url,
+            quality,
+            format,
+            url,
+            segments,
+            hls_sequence: None,
+        };
+        tokio::spawn(task.run(stop_rx));
+        Ok(Recorder {
+            shared,
+            stop: Some(stop_tx),
+        })
+    }
+
+    pub fn info(&self) -> Option<RecordInfo> {
+        self.shared.lock().ok().map(|shared| shared.info.clone())
+    }
+
+    /// 把事件写到旁路文件，录制还没开始（没收到视频数据）时忽略
+    pub fn record_event(&self, event: &LiveEvent) {
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        if shared.info.started_at == 0 || matches!(shared.info.state, RecordState::Stopped { .. }) {
+            return;
+        }
+        if let Some(types) = &shared.sidecar_types {
+            if !types.iter().any(|t| t == event.kind()) {
+                return;
+            }
+        }
+        let now = now_millis();
+        let line = SidecarLine {
+            offset_ms: now.saturating_sub(shared.info.started_at),
+            segment: shared.info.segments.len(),
+            segment_offset_ms: now.saturating_sub(shared.segment_started_at),
+            received_at: now,
+            event,
+        };
+        let result = serde_json::to_string(&line)
+            .map_err(io::Error::from)
+            .and_then(|json| {
+                writeln!(shared.sidecar, "{}", json)?;
+                shared.sidecar.flush()
+            });
+        if let Err(e) = result {
+            eprintln!("❌ [录制] 写入旁路文件失败: {}", e);
+        }
+    }
+
+    /// 停止录制，已经写入的文件会保留
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// 按清晰度和格式选出拉流地址，返回实际使用的清晰度和格式
+fn pick_stream(
+    sources: &StreamSources,
+    quality: Quality,
+    format: StreamFormat,
+) -> Option<(String, Quality, StreamFormat)> {
+    let source = sources.pick(quality)?;
+    let flv = source.flv.clone().map(|url| (url, StreamFormat::Flv));
+    let hls = source.hls.clone().map(|url| (url, StreamFormat::Hls));
+    let (url, format) = match format {
+        StreamFormat::Flv => flv.or(hls),
+        StreamFormat::Hls => hls.or(flv),
+    }?;
+    Some((url, source.quality, format))
+}
+
+// 一次下载的结果，出错时返回 Err(原因)
+pub(super) enum Outcome {
+    Stopped,
+    // 服务器正常结束了这条流
+    Ended,
+}
+
+// 等待 future 完成，期间收到停止信号时返回 None
+pub(super) async fn or_stop<T>(
+    stop: &mut oneshot::Receiver<()>,
+    future: impl Future<Output = T>,
+) -> Option<T> {
+    tokio::select! {
+        value = future => Some(value),
+        _ = stop => None,
+    }
+}
+
+// 分段文件，达到大小或者时长后由调用方在合适的位置（关键帧、分片边界）换新文件
+pub(super) struct Segments {
+    shared: Arc<Mutex<Shared>>,
+    tx: mpsc::UnboundedSender<RecordState>,
+    dir: PathBuf,
+    prefix: String,
+    extension: &'static str,
+    max_bytes: Option<u64>,
+    max_duration: Option<Duration>,
+    file: Option<BufWriter<File>>,
+    bytes: u64,
+    opened_at: Instant,
+}
+
+impl Segments {
+    pub(super) fn is_open(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub(super) fn is_full(&self) -> bool {
+        self.file.is_some()
+            && (self.max_bytes.is_some_and(|max| self.bytes >= max)
+                || self
+                    .max_duration
+                    .is_some_and(|max| self.opened_at.elapsed() >= max))
+    }
+
+    /// 关闭当前分段，新建下一个
+    pub(super) fn open_next(&mut self) -> io::Result<()> {
+        self.close();
+        let Ok(mut shared) = self.shared.lock() else {
+            return Err(io::Error::other("录制状态不可用"));
+        };
+        let index = shared.info.segments.len() + 1;
+        let path = self
+            .dir
+            .join(format!("{}-{:03}.{}", self.prefix, index, self.extension));
+        self.file = Some(BufWriter::new(File::create(&path)?));
+        self.bytes = 0;
+        self.opened_at = Instant::now();
+
+        let now = now_millis();
+        if shared.info.started_at == 0 {
+            shared.info.started_at = now;
+        }
+        shared.segment_started_at = now;
+        let path = path.to_string_lossy().to_string();
+        shared.info.segments.push(path.clone());
+        eprintln!("📁 [录制] 第 {} 段: {}", index, path);
+        let state = RecordState::Recording {
+            segment: index,
+            path,
+        };
+        shared.info.state = state.clone();
+        let _ = self.tx.send(state);
+        Ok(())
+    }
+
+    pub(super) fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        file.write_all(data)?;
+        self.bytes += data.len() as u64;
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.info.bytes += data.len() as u64;
+        }
+        Ok(())
+    }
+
+    pub(super) fn close(&mut self) {
+        if let Some(mut file) = self.file.take() {
+            if let Err(e) = file.flush() {
+                eprintln!("❌ [录制] 写入文件失败: {}", e);
+            }
+        }
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.shared.lock().map(|s| s.info.bytes).unwrap_or(0)
+    }
+
+    fn set_state(&self, state: RecordState) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.info.state = state.clone();
+        }
+        let _ = self.tx.send(state);
+    }
+}
+
+struct Task {
+    room_url: Option<String>,
+    quality: Quality,
+    format: StreamFormat,
+    url: String,
+    segments: Segments,
+    // HLS 写入的最后一个分片序号，重连后接着写
+    hls_sequence: Option<u64>,
+}
+
+impl Task {
+    async fn run(mut self, mut stop: oneshot::Receiver<()>) {
+        let reason = self.record(&mut stop).await;
+        self.segments.close();
+        eprintln!("⏹️ [录制] 录制结束: {}", reason);
+        self.segments.set_state(RecordState::Stopped { reason });
+    }
+
+    // 下载直播流，断开后重新连接，返回结束的原因
+    async fn record(&mut self, stop: &mut oneshot::Receiver<()>) -> String {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REFERER,
+            HeaderValue::from_static("https://live.douyin.com/"),
+        );
+        let client = match reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .default_headers(headers)
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => return format!("创建 HTTP 客户端失败: {}", e),
+        };
+        let mut attempt = 0;
+        loop {
+            let received = self.segments.total_bytes();
+            let result = match self.format {
+                StreamFormat::Flv => {
+                    flv::download(&client, &self.url, &mut self.segments, stop).await
+                }
+                StreamFormat::Hls => {
+                    let sequence = &mut self.hls_sequence;
+                    hls::download(&client, &self.url, sequence, &mut self.segments, stop).await
+                }
+            };
+            let reason = match result {
+                Ok(Outcome::Stopped) => return "主动停止".to_string(),
+                Ok(Outcome::Ended) => "直播流已结束".to_string(),
+                Err(e) => e,
+            };
+            // 收到过数据说明连接是通的，重新计算重连次数
+            if self.segments.total_bytes() > received {
+                attempt = 0;
+            }
+            attempt += 1;
+            if attempt > MAX_RECONNECT_ATTEMPTS {
+                return format!("重连 {} 次都失败了: {}", MAX_RECONNECT_ATTEMPTS, reason);
+            }
+
+            let delay = backoff_delay(attempt);
+            eprintln!(
+                "🔁 [录制] 直播流断开（{}），{:?} 后第 {} 次重连",
+                reason, delay, attempt
+            );
+            self.segments.set_state(RecordState::Reconnecting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+                reason,
+            });
+            if or_stop(stop, tokio::time::sleep(delay)).await.is_none() {
+                return "主动停止".to_string();
+            }
+
+            if attempt >= REFRESH_AFTER_ATTEMPTS {
+                if let Some(room_url) = self.room_url.clone() {
+                    match self.refresh(&room_url).await {
+                        Ok(Some(url)) => self.url = url,
+                        Ok(None) => return "直播已结束".to_string(),
+                        Err(e) => eprintln!("⚠️ [录制] 重新获取拉流地址失败: {}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    // 重新获取拉流地址，不在直播时返回 None
+    async fn refresh(&self, room_url: &str) -> Result<Option<String>, String> {
+        let info = DouYinReq::new(room_url)
+            .get_room_info()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !info.status.is_live() {
+            return Ok(None);
+        }
+        match pick_stream(&info.streams, self.quality, self.format) {
+            Some((url, _, format)) if format == self.format => Ok(Some(url)),
+            _ => Err("没有同样格式的拉流地址".to_string()),
+        }
+    }
+}
+
+// 第 attempt 次重连前等待的时间，在 [d/2, d] 之间随机
+fn backoff_delay(attempt: u32) -> Duration {
+    let exp = RECONNECT_BASE.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let max = exp.min(RECONNECT_MAX).as_millis() as u64;
+    let half = max / 2;
+    Duration::from_millis(half + rand::thread_rng().gen_range(0..=half))
+}
+
+#[cfg(test)]
+impl Segments {
+    // 测试用的分段，写到临时目录里，max_bytes 控制什么时候换新文件
+    pub(super) fn for_test(name: &str, max_bytes: Option<u64>) -> Self {
+        let dir = std::env::temp_dir().join(format!("livebox-{}-{}", name, now_millis()));
+        fs::create_dir_all(&dir).unwrap();
+        let sidecar = BufWriter::new(File::create(dir.join("test.jsonl")).unwrap());
+        let shared = Arc::new(Mutex::new(Shared {
+            info: RecordInfo {
+                room_id: "test".to_string(),
+                quality: Quality::Origin,
+                format: StreamFormat::Flv,
+                stream_url: String::new(),
+                started_at: 0,
+                bytes: 0,
+                segments: Vec::new(),
+                sidecar_path: String::new(),
+                state: RecordState::Connecting,
+            },
+            sidecar,
+            sidecar_types: None,
+            segment_started_at: 0,
+        }));
+        Segments {
+            shared,
+            tx: mpsc::unbounded_channel().0,
+            dir,
+            prefix: "test".to_string(),
+            extension: "flv",
+            max_bytes,
+            max_duration: None,
+            file: None,
+            bytes: 0,
+            opened_at: Instant::now(),
+        }
+    }
+
+    // 关闭并读出所有分段文件的内容，然后删掉临时目录
+    pub(super) fn finish_test(mut self) -> Vec<Vec<u8>> {
+        self.close();
+        let paths = self.shared.lock().unwrap().info.segments.clone();
+        let files = paths.iter().map(|p| fs::read(p).unwrap()).collect();
+        let _ = fs::remove_dir_all(&self.dir);
+        files
+    }
+}
@@ -0,0 +1,338 @@
+// 直播间地址解析：把用户输入的各种形式统一成 live.douyin.com/<web_rid>
+//
+// 支持：
+// - 纯数字：较短的是 web_rid（地址栏里的数字），19 位左右的是 room_id
+// - live.douyin.com/<web_rid>、www.douyin.com/root/live/<web_rid> 等网页地址
+// - v.douyin.com 短链接（跟随跳转后再解析）
+// - www.douyin.com/user/<sec_uid> 主页地址（主播需要正在直播）
+// - webcast.amemv.com 分享链接
+// - 带有上面这些链接的分享文案
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+use crate::webcast::USER_AGENT;
+
+// room_id 的位数，web_rid 一般不超过 12 位
+const ROOM_ID_MIN_LEN: usize = 16;
+
+// 分享页接口，可以用 room_id 或主播的 sec_uid 查到房间信息
+const REFLOW_API: &str = "https://webcast.amemv.com/webcast/room/reflow/info/";
+// 网页版进房接口，可以用 web_rid 查到 room_id
+const ENTER_API: &str = "https://live.douyin.com/webcast/room/web/enter/";
+
+type ResolveError = Box<dyn std::error::Error + Send + Sync>;
+
+// 从输入里直接看出来的信息，还没有访问网络
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoomLink {
+    // live.douyin.com/ 后面的数字
+    WebRid(String),
+    // 直播间的内部 id，sec_uid 来自分享链接，有的话查询更可靠
+    RoomId {
+        room_id: String,
+        sec_uid: Option<String>,
+    },
+    // 主播主页
+    Profile { sec_uid: String },
+    // 需要跟随跳转的短链接
+    ShortLink(String),
+}
+
+// 解析完成的直播间
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedRoom {
+    pub web_rid: String,
+    // 没有开播或者接口查不到时为空，连接前由 get_room_info 获取
+    pub room_id: Option<String>,
+    // 规范化后的直播间地址
+    pub url: String,
+}
+
+/// 直播间地址
+pub fn live_url(web_rid: &str) -> String {
+    format!("https://live.douyin.com/{}", web_rid)
+}
+
+/// 解析用户输入，不访问网络
+pub fn parse_room_input(input: &str) -> Result<RoomLink, String> {
+    let input = input.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+    if input.is_empty() {
+        return Err("直播间地址为空".to_string());
+    }
+    if is_digits(input) {
+        return Ok(numeric_link(input));
+    }
+
+    // 分享文案里取出第一个链接，没有协议的补上 https
+    let url = extract_url(input).ok_or_else(|| format!("无法识别的直播间地址: {}", input))?;
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("直播间地址格式不对: {}", e))?;
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let query = |key: &str| {
+        parsed
+            .query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    match host.as_str() {
+        "v.douyin.com" => return Ok(RoomLink::ShortLink(url.clone())),
+        "live.douyin.com" => {
+            if let Some(id) = segments.first().filter(|s| is_digits(s)) {
+                return Ok(RoomLink::WebRid(id.to_string()));
+            }
+            if let Some(room_id) = query("room_id") {
+                return Ok(RoomLink::RoomId {
+                    room_id,
+                    sec_uid: None,
+                });
+            }
+        }
+        "www.douyin.com" | "douyin.com" => match segments.as_slice() {
+            // 例如 /root/live/123、/follow/live/123
+            [.., "live", id] if is_digits(id) => return Ok(RoomLink::WebRid(id.to_string())),
+            ["user", sec_uid, ..] if *sec_uid != "self" => {
+                return Ok(RoomLink::Profile {
+                    sec_uid: sec_uid.to_string(),
+                })
+            }
+            _ => {}
+        },
+        // 例如 /douyin/webcast/reflow/<room_id>?sec_user_id=...
+        "webcast.amemv.com" | "webcast.huoshan.com" | "www.iesdouyin.com" | "live.amemv.com" => {
+            let sec_uid = query("sec_user_id").or_else(|| query("sec_uid"));
+            if let Some(room_id) = query("room_id")
+                .or_else(|| segments.last().filter(|s| is_digits(s)).map(|s| s.to_string()))
+            {
+                return Ok(RoomLink::RoomId { room_id, sec_uid });
+            }
+            // iesdouyin 的主页分享 /share/user/<sec_uid>
+            if let ["share", "user", sec_uid, ..] = segments.as_slice() {
+                return Ok(RoomLink::Profile {
+                    sec_uid: sec_uid.to_string(),
+                });
+            }
+            if let Some(sec_uid) = sec_uid {
+                return Ok(RoomLink::Profile { sec_uid });
+            }
+        }
+        _ => {}
+    }
+    Err(format!("无法识别的直播间地址: {}", input))
+}
+
+/// 解析用户输入，短链接、room_id 和主页地址会访问网络查出 web_rid
+pub async fn resolve_room(input: &str) -> Result<ResolvedRoom, ResolveError> {
+    let mut link = parse_room_input(input)?;
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .cookie_store(true)
+        .build()?;
+
+    // 短链接跳转后的地址再解析一次
+    if let RoomLink::ShortLink(url) = &link {
+        let response = client.get(url).send().await?;
+        let target = response.url().to_string();
+        eprintln!("🔗 [地址解析] 短链接跳转到: {}", target);
+        link = match parse_room_input(&target)? {
+            RoomLink::ShortLink(_) => return Err(format!("短链接没有跳转到直播间: {}", target).into()),
+            link => link,
+        };
+    }
+
+    let resolved = match link {
+        RoomLink::WebRid(web_rid) => {
+            let room_id = enter_room_id(&client, &web_rid).await.unwrap_or_else(|e| {
+                eprintln!("⚠️ [地址解析] 查询 room_id 失败: {}", e);
+                None
+            });
+            ResolvedRoom {
+                url: live_url(&web_rid),
+                web_rid,
+                room_id,
+            }
+        }
+        RoomLink::RoomId { room_id, sec_uid } => {
+            reflow_room(&client, Some(&room_id), sec_uid.as_deref()).await?
+        }
+        RoomLink::Profile { sec_uid } => reflow_room(&client, None, Some(&sec_uid)).await?,
+        RoomLink::ShortLink(_) => unreachable!("短链接已经在上面展开"),
+    };
+    eprintln!(
+        "🔗 [地址解析] {} -> web_rid: {}, room_id: {}",
+        input,
+        resolved.web_rid,
+        resolved.room_id.as_deref().unwrap_or("(未知)")
+    );
+    Ok(resolved)
+}
+
+// 用分享页接口查房间，返回 web_rid 和 room_id
+async fn reflow_room(
+    client: &reqwest::Client,
+    room_id: Option<&str>,
+    sec_uid: Option<&str>,
+) -> Result<ResolvedRoom, ResolveError> {
+    let mut params = vec![("type_id", "0"), ("live_id", "1"), ("app_id", "1128")];
+    if let Some(room_id) = room_id {
+        params.push(("room_id", room_id));
+    }
+    if let Some(sec_uid) = sec_uid {
+        params.push(("sec_user_id", sec_uid));
+    }
+    let body: serde_json::Value = client
+        .get(REFLOW_API)
+        .query(&params)
+        .send()
+        .await?
+        .json()
+        .await?;
+    let room = body
+        .pointer("/data/room")
+        .ok_or("分享页接口没有返回房间信息，主播可能没有开播")?;
+    let web_rid = room
+        .pointer("/owner/web_rid")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .ok_or("分享页接口没有返回 web_rid")?
+        .to_string();
+    let room_id = room
+        .get("id_str")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty() && *v != "0")
+        .map(|v| v.to_string())
+        .or_else(|| room_id.map(|v| v.to_string()));
+    Ok(ResolvedRoom {
+        url: live_url(&web_rid),
+        web_rid,
+        room_id,
+    })
+}
+
+// 用网页版进房接口查 room_id，需要先访问一次直播间拿到 ttwid
+async fn enter_room_id(
+    client: &reqwest::Client,
+    web_rid: &str,
+) -> Result<Option<String>, ResolveError> {
+    client.get(live_url(web_rid)).send().await?;
+    let body: serde_json::Value = client
+        .get(ENTER_API)
+        .query(&[
+            ("aid", "6383"),
+            ("app_name", "douyin_web"),
+            ("live_id", "1"),
+            ("device_platform", "web"),
+            ("enter_from", "web_live"),
+            ("web_rid", web_rid),
+        ])
+        .header("referer", live_url(web_rid))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let room_id = body
+        .pointer("/data/data/0/id_str")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+    Ok(room_id)
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+fn numeric_link(id: &str) -> RoomLink {
+    if id.len() >= ROOM_ID_MIN_LEN {
+        RoomLink::RoomId {
+            room_id: id.to_string(),
+            sec_uid: None,
+        }
+    } else {
+        RoomLink::WebRid(id.to_string())
+    }
+}
+
+// 从输入里找出第一个链接，补全协议，和 Python 版的 normalize_url 一样容忍常见的输入错误
+fn extract_url(input: &str) -> Option<String> {
+    static URL_RE: OnceLock<Regex> = OnceLock::new();
+    let re = URL_RE.get_or_init(|| {
+        // 域名前面必须是开头或分隔符，避免 notdouyin.com 这样的域名被当成 douyin.com
+        Regex::new(r"(?i)(?:^|[^a-z0-9.-])(?:https?://|://)?((?:[a-z0-9-]+\.)*(?:douyin|amemv|iesdouyin|huoshan)\.com(?:/[^\s，。！]*)?)")
+            .unwrap()
+    });
+    let captures = re.captures(input)?;
+    Some(format!("https://{}", &captures[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn web_rid(id: &str) -> Result<RoomLink, String> {
+        Ok(RoomLink::WebRid(id.to_string()))
+    }
+
+    fn room_id(id: &str, sec_uid: Option<&str>) -> Result<RoomLink, String> {
+        Ok(RoomLink::RoomId {
+            room_id: id.to_string(),
+            sec_uid: sec_uid.map(|s| s.to_string()),
+        })
+    }
+
+    fn profile(sec_uid: &str) -> Result<RoomLink, String> {
+        Ok(RoomLink::Profile {
+            sec_uid: sec_uid.to_string(),
+        })
+    }
+
+    #[test]
+    fn parse_room_inputs() {
+        let cases = [
+            ("972176515698", web_rid("972176515698")),
+            (" '972176515698' ", web_rid("972176515698")),
+            ("7376429659866598196", room_id("7376429659866598196", None)),
+            ("https://live.douyin.com/972176515698", web_rid("972176515698")),
+            ("http://live.douyin.com/972176515698?enter_from_merge=web_search", web_rid("972176515698")),
+            ("live.douyin.com/972176515698", web_rid("972176515698")),
+            ("s://live.douyin.com/972176515698", web_rid("972176515698")),
+            ("https://live.douyin.com/?room_id=7376429659866598196", room_id("7376429659866598196", None)),
+            ("https://www.douyin.com/root/live/972176515698", web_rid("972176515698")),
+            ("https://www.douyin.com/follow/live/972176515698?from_tab_name=live", web_rid("972176515698")),
+            (
+                "https://www.douyin.com/user/MS4wLjABAAAAabc_DEF-123?from_tab_name=main",
+                profile("MS4wLjABAAAAabc_DEF-123"),
+            ),
+            (
+                "https://www.iesdouyin.com/share/user/MS4wLjABAAAAabc?u_code=1",
+                profile("MS4wLjABAAAAabc"),
+            ),
+            ("https://v.douyin.com/iRNBho6u/", Ok(RoomLink::ShortLink("https://v.douyin.com/iRNBho6u/".to_string()))),
+            (
+                "7- 长按复制此条消息，打开抖音搜索，查看TA的更多作品。 https://v.douyin.com/iRNBho6u/ 0@5.com :8pm",
+                Ok(RoomLink::ShortLink("https://v.douyin.com/iRNBho6u/".to_string())),
+            ),
+            (
+                "https://webcast.amemv.com/douyin/webcast/reflow/7376429659866598196?u_code=abc&sec_user_id=MS4wLjABAAAAxyz",
+                room_id("7376429659866598196", Some("MS4wLjABAAAAxyz")),
+            ),
+            (
+                "https://webcast.amemv.com/webcast/reflow/?room_id=7376429659866598196",
+                room_id("7376429659866598196", None),
+            ),
+            ("", Err("直播间地址为空".to_string())),
+            ("https://www.douyin.com/", Err("无法识别的直播间地址: https://www.douyin.com/".to_string())),
+            ("https://example.com/972176515698", Err("无法识别的直播间地址: https://example.com/972176515698".to_string())),
+            ("https://notdouyin.com/x", Err("无法识别的直播间地址: https://notdouyin.com/x".to_string())),
+            ("live.notdouyin.com/972176515698", Err("无法识别的直播间地址: live.notdouyin.com/972176515698".to_string())),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_room_input(input), expected, "输入: {}", input);
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// 直播间状态，由 room.status 等字段推断
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomStatus {
+    // 还没有开播
+    Preparing,
+    Live,
+    // 主播暂时离开，弹幕还能连上
+    Paused,
+    Ended,
+    // 违规被封禁或者打码
+    Banned,
+    // 页面里没有直播间也没有主播
+    #[default]
+    NotFound,
+}
+
+impl RoomStatus {
+    /// 页面里 room.status 的值：1 准备中、2 直播中、3 暂停、4 已结束
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 | 1 => RoomStatus::Preparing,
+            2 => RoomStatus::Live,
+            3 => RoomStatus::Paused,
+            _ => RoomStatus::Ended,
+        }
+    }
+
+    /// 是否可以连接弹幕
+    pub fn is_live(self) -> bool {
+        matches!(self, RoomStatus::Live | RoomStatus::Paused)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoomStatus::Preparing => "未开播",
+            RoomStatus::Live => "直播中",
+            RoomStatus::Paused => "主播暂时离开",
+            RoomStatus::Ended => "直播已结束",
+            RoomStatus::Banned => "直播间已被封禁",
+            RoomStatus::NotFound => "直播间不存在",
+        }
+    }
+}
+
+// 图片，url_list 里是同一张图的多个 CDN 地址
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Image {
+    #[serde(deserialize_with = "nullable")]
+    pub url_list: Vec<String>,
+}
+
+impl Image {
+    pub fn url(&self) -> Option<&str> {
+        self.url_list.first().map(String::as_str)
+    }
+}
+
+// 主播信息，开播时是 room.owner，停播时页面里只有 roomInfo.anchor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Anchor {
+    pub id_str: String,
+    pub sec_uid: String,
+    pub nickname: String,
+    #[serde(deserialize_with = "nullable")]
+    pub avatar_thumb: Image,
+}
+
+// 直播流地址，flv_pull_url / hls_pull_url_map 的 key 是清晰度（FULL_HD1、HD1、SD1、SD2）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamUrl {
+    pub default_resolution: String,
+    #[serde(deserialize_with = "nullable")]
+    pub flv_pull_url: BTreeMap<String, String>,
+    #[serde(deserialize_with = "nullable")]
+    pub hls_pull_url_map: BTreeMap<String, String>,
+    pub hls_pull_url: String,
+    #[serde(deserialize_with = "nullable")]
+    pub live_core_sdk_data: LiveCoreSdkData,
+}
+
+// 播放器 SDK 用的数据，stream_data 是 JSON 字符串，里面有 flv_pull_url 没有的原画
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveCoreSdkData {
+    #[serde(deserialize_with = "nullable")]
+    pub pull_data: PullData,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PullData {
+    pub stream_data: String,
+    #[serde(deserialize_with = "nullable")]
+    pub options: PullOptions,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PullOptions {
+    #[serde(deserialize_with = "nullable")]
+    pub default_quality: SdkQuality,
+    #[serde(deserialize_with = "nullable")]
+    pub qualities: Vec<SdkQuality>,
+}
+
+// sdk_key 是 origin、uhd、hd、sd、ld，name 是界面上显示的原画、蓝光等
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SdkQuality {
+    pub name: String,
+    pub sdk_key: String,
+    pub resolution: String,
+}
+
+impl StreamUrl {
+    /// 默认清晰度的 FLV 地址，和网页播放器默认播放的一致
+    pub fn default_flv(&self) -> Option<&str> {
+        self.flv_pull_url
+            .get(&self.default_resolution)
+            .or_else(|| self.flv_pull_url.values().next())
+            .map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomStats {
+    pub total_user_str: String,
+    pub user_count_str: String,
+    pub like_count: u64,
+}
+
+// 直播间信息，对应页面 roomStore.roomInfo.room；字段名和抖音返回的一致，序列化后前端可以直接用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomInfo {
+    #[serde(rename = "id_str")]
+    pub room_id: String,
+    // room 对象里没有，解析页面时从 roomInfo.web_rid 补上
+    pub web_rid: String,
+    pub status: i32,
+    // 不为 0 时直播间因为违规被屏蔽
+    pub mosaic_status: i32,
+    pub title: String,
+    pub user_count_str: String,
+    #[serde(deserialize_with = "nullable")]
+    pub cover: Image,
+    #[serde(deserialize_with = "nullable")]
+    pub owner: Anchor,
+    #[serde(deserialize_with = "nullable")]
+    pub stream_url: StreamUrl,
+    #[serde(deserialize_with = "nullable")]
+    pub stats: RoomStats,
+}
+
+impl RoomInfo {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// 直播间状态；停播后页面里可能只有主播信息，这时算已结束
+    pub fn room_status(&self) -> RoomStatus {
+        if self.room_id.is_empty() {
+            return if self.owner.id_str.is_empty() {
+                RoomStatus::NotFound
+            } else {
+                RoomStatus::Ended
+            };
+        }
+        if self.mosaic_status != 0 {
+            return RoomStatus::Banned;
+        }
+        RoomStatus::from_code(self.status)
+    }
+}
+
+// 从直播间页面解析出的信息
+#[derive(Debug, Clone)]
+pub struct RoomPage {
+    pub room: RoomInfo,
+    // 给前端的原始 JSON：开播时是 room 对象，停播时是主播信息，和原来正则取到的内容一致
+    pub raw: String,
+}
+
+/// 从直播间页面里找到 roomStore.roomInfo 并解析
+///
+/// 依次尝试 RENDER_DATA 脚本（urlencode 过的 JSON）、流式渲染的 self.__pace_f 数据块和页面原文
+pub fn parse_room_page(html: &str) -> Option<RoomPage> {
+    let mut sources = Vec::new();
+    if let Some(data) = render_data(html) {
+        sources.push(data);
+    }
+    if let Some(data) = pace_data(html) {
+        sources.push(data);
+    }
+    sources.push(html.to_string());
+    // 最后按原来的办法把转义的引号直接替换掉，标题里带引号时可能解析不出来
+    sources.push(html.replace(r#"\""#, r#"""#));
+
+    sources
+        .iter()
+        .find_map(|text| find_room_info(text))
+        .and_then(|info| room_page(&info))
+}
+
+fn room_page(info: &Value) -> Option<RoomPage> {
+    let room_value = info.get("room").filter(|v| v.is_object());
+    let anchor_value = info.get("anchor").filter(|v| v.is_object());
+    let mut room: RoomInfo = match room_value {
+        Some(value) => serde_json::from_value(value.clone()).ok()?,
+        None => RoomInfo::default(),
+    };
+    let anchor: Option<Anchor> = anchor_value.and_then(|v| serde_json::from_value(v.clone()).ok());
+    if room.room_id.is_empty() {
+        room.room_id = string_field(info, "roomId");
+    }
+    if room.web_rid.is_empty() {
+        room.web_rid = string_field(info, "web_rid");
+    }
+    if room.owner.id_str.is_empty() {
+        if let Some(anchor) = &anchor {
+            room.owner = anchor.clone();
+        }
+    }
+
+    // 没有开播或者没有 room 对象时返回主播信息
+    let raw = match (room_value, anchor_value) {
+        (Some(_), Some(anchor)) if !room.room_status().is_live() => anchor.to_string(),
+        (Some(value), _) => value.to_string(),
+        (None, Some(anchor)) => anchor.to_string(),
+        (None, None) => "{}".to_string(),
+    };
+    Some(RoomPage { room, raw })
+}
+
+// id 有时是数字有时是字符串
+fn string_field(value: &Value, key: &str) -> String {
+    match value.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// <script id="RENDER_DATA" type="application/json">...</script>
+fn render_data(html: &str) -> Option<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r#"<script[^>]*id="RENDER_DATA"[^>]*>([^<]*)</script>"#).unwrap()
+    });
+    let data = re.captures(html)?.get(1)?.as_str();
+    urlencoding::decode(data).ok().map(|s| s.into_owned())
+}
+
+// self.__pace_f.push([1,"..."]) 里的字符串是 JS 字面量，按 JSON 字符串解码后拼起来
+fn pace_data(html: &str) -> Option<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r#"self\.__pace_f\.push\(\[1,\s*("(?:[^"\\]|\\.)*")\]\)"#).unwrap()
+    });
+    let data: String = re
+        .captures_iter(html)
+        .filter_map(|c| serde_json::from_str::<String>(c.get(1)?.as_str()).ok())
+        .collect();
+    (!data.is_empty()).then_some(data)
+}
+
+// 找到第一个 "roomInfo":{...}，优先取有 room 或 anchor 的
+fn find_room_info(text: &str) -> Option<Value> {
+    const KEY: &str = "\"roomInfo\":";
+    let mut empty = None;
+    for (start, _) in text.match_indices(KEY) {
+        let Some(value) = json_object(&text[start + KEY.len()..])
+            .and_then(|object| serde_json::from_str::<Value>(object).ok())
+        else {
+            continue;
+        };
+        let found = ["room", "anchor"]
+            .iter()
+            .any(|key| value.get(key).is_some_and(Value::is_object));
+        if found {
+            return Some(value);
+        }
+        empty.get_or_insert(value);
+    }
+    empty
+}
+
+// 从 { 开始取出一个完整的 JSON 对象，跳过字符串里的括号
+fn json_object(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+    if !text.starts_with('{') {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, b) in text.bytes().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// 字段是 null 时当作默认值
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOM: &str = include_str!("../../analysis/roomInfo.json");
+    const ANCHOR: &str = include_str!("../../analysis/stopInfo.json");
+
+    // 模拟页面里的 roomStore，room 和 anchor 用抓下来的数据
+    fn store(room: &str, anchor: &str) -> String {
+        format!(
+            r#"{{"roomStore":{{"roomInfo":{{"room":{},"roomId":"7384780141332876032","web_rid":"646454278948","anchor":{}}},"toolbar_data":{{}}}}}}"#,
+            room, anchor
+        )
+    }
+
+    #[test]
+    fn parses_room_fixture() {
+        let room = RoomInfo::from_json(ROOM).unwrap();
+        assert_eq!(room.room_id, "7384780141332876032");
+        assert_eq!(room.status, 2);
+        assert_eq!(room.title, "路人局稳健打法");
+        assert_eq!(room.user_count_str, "1w+");
+        assert_eq!(room.owner.nickname, "冷宴华");
+        assert_eq!(room.owner.id_str, "3412508281668360");
+        assert!(room.cover.url().unwrap().starts_with("https://"));
+        assert!(room.owner.avatar_thumb.url().is_some());
+        assert_eq!(room.stats.total_user_str, "10万+");
+
+        let stream = &room.stream_url;
+        assert_eq!(stream.default_resolution, "HD1");
+        assert_eq!(stream.flv_pull_url.len(), 4);
+        assert_eq!(
+            stream.default_flv(),
+            stream.flv_pull_url.get("HD1").map(String::as_str)
+        );
+        assert!(stream.hls_pull_url_map.contains_key("FULL_HD1"));
+        assert!(stream.hls_pull_url.starts_with("http"));
+    }
+
+    #[test]
+    fn parses_anchor_fixture() {
+        let anchor: Anchor = serde_json::from_str(ANCHOR).unwrap();
+        assert_eq!(anchor.id_str, "60544338179");
+        assert_eq!(anchor.nickname, "霖哥（六指1v4）");
+        assert!(anchor.sec_uid.starts_with("MS4wLjABAAAA"));
+        assert_eq!(anchor.avatar_thumb.url_list.len(), 3);
+    }
+
+    #[test]
+    fn parses_streamed_page() {
+        let data = store(ROOM, ANCHOR);
+        // 数据被拆成两块推送，断开的位置在 JSON 中间
+        let (a, b) = data.split_at(data.len() / 2);
+        let html = format!(
+            "<script>self.__pace_f.push([1,{}])</script><script>self.__pace_f.push([1,{}])</script>",
+            serde_json::to_string(a).unwrap(),
+            serde_json::to_string(b).unwrap()
+        );
+        let page = parse_room_page(&html).unwrap();
+        assert_eq!(page.room.room_id, "7384780141332876032");
+        assert_eq!(page.room.web_rid, "646454278948");
+        assert_eq!(page.room.owner.nickname, "冷宴华");
+        let raw: Value = serde_json::from_str(&page.raw).unwrap();
+        assert_eq!(raw["stats"]["like_count"], 0);
+    }
+
+    #[test]
+    fn parses_render_data() {
+        let html = format!(
+            r#"<html><script id="RENDER_DATA" type="application/json">{}</script></html>"#,
+            urlencoding::encode(&store(ROOM, ANCHOR))
+        );
+        let page = parse_room_page(&html).unwrap();
+        assert_eq!(page.room.title, "路人局稳健打法");
+        assert_eq!(page.room.stream_url.default_resolution, "HD1");
+    }
+
+    #[test]
+    fn ended_room_returns_anchor() {
+        let room = r#"{"id_str":"7384780141332876032","status":4,"title":"","owner":null,"stream_url":null}"#;
+        let page = parse_room_page(&store(room, ANCHOR)).unwrap();
+        assert_eq!(page.room.room_status(), RoomStatus::Ended);
+        assert_eq!(page.room.owner.nickname, "霖哥（六指1v4）");
+        let raw: Anchor = serde_json::from_str(&page.raw).unwrap();
+        assert_eq!(raw.id_str, "60544338179");
+    }
+
+    #[test]
+    fn missing_room_info() {
+        assert!(parse_room_page("<html>Access Denied</html>").is_none());
+        let page = parse_room_page(r#"{"roomInfo":{"toolbar_data":{}}}"#).unwrap();
+        assert_eq!(page.room.room_status(), RoomStatus::NotFound);
+    }
+
+    #[test]
+    fn room_status() {
+        let cases = [
+            (r#"{"id_str":"1","status":1}"#, RoomStatus::Preparing),
+            (r#"{"id_str":"1","status":2}"#, RoomStatus::Live),
+            (r#"{"id_str":"1","status":3}"#, RoomStatus::Paused),
+            (r#"{"id_str":"1","status":4}"#, RoomStatus::Ended),
+            (
+                r#"{"id_str":"1","status":2,"mosaic_status":1}"#,
+                RoomStatus::Banned,
+            ),
+            (r#"{"owner":{"id_str":"60544338179"}}"#, RoomStatus::Ended),
+            (r#"{}"#, RoomStatus::NotFound),
+        ];
+        for (json, status) in cases {
+            assert_eq!(
+                RoomInfo::from_json(json).unwrap().room_status(),
+                status,
+                "{}",
+                json
+            );
+        }
+        assert_eq!(
+            RoomInfo::from_json(ROOM).unwrap().room_status(),
+            RoomStatus::Live
+        );
+
+        // 停播后只剩主播信息的页面
+        let html = format!(
+            r#"{{"roomInfo":{{"web_rid":"646454278948","anchor":{}}}}}"#,
+            ANCHOR
+        );
+        let page = parse_room_page(&html).unwrap();
+        assert_eq!(page.room.room_status(), RoomStatus::Ended);
+        assert_eq!(page.room.owner.nickname, "霖哥（六指1v4）");
+    }
+}
@@ -1,4 +1,6 @@
-use crate::command::model::LiveInfo;
+use crate::model::LiveInfo;
+use crate::resolver::{parse_room_input, RoomLink};
+use crate::room::parse_room_page;
 use crate::utils::cookie_store::CookieStore;
 use regex::Regex;
 use reqwest::Client;
@@ -26,10 +28,10 @@ impl DouYinReq {
     }
 
     pub async fn get_room_info(&mut self) -> Result<LiveInfo, Box<dyn std::error::Error>> {
-        println!("获取直播间的room_info: {}", self.room_url);
+        eprintln!("获取直播间的room_info: {}", self.room_url);
 
         // 第一步：先访问 douyin.com 主页，获取必要的 Cookie（避免 Access Denied）
-        println!("步骤1: 访问 douyin.com 获取初始 Cookie...");
+        eprintln!("步骤1: 访问 douyin.com 获取初始 Cookie...");
         let mut home_headers = reqwest::header::HeaderMap::new();
         home_headers.insert("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7".parse()?);
         // 移除 accept-encoding 以获取未压缩响应（reqwest 需要额外 features 才能自动解压）
@@ -54,37 +56,37 @@ impl DouYinReq {
         let mut collected_cookies = Vec::new();
         for c in home_cookies {
             collected_cookies.push(format!("{}={}", c.name(), c.value()));
-            println!("  获取到 Cookie: {}", c.name());
+            eprintln!("  获取到 Cookie: {}", c.name());
         }
 
         // 第二步：使用获取的 Cookie 访问直播间页面
-        println!("步骤2: 使用 Cookie 访问直播间...");
+        eprintln!("步骤2: 使用 Cookie 访问直播间...");
 
         // 尝试加载用户保存的 Cookie
         let saved_cookies = if let Ok(cookie_path) = CookieStore::get_default_path() {
-            println!("📁 Cookie 文件路径: {:?}", cookie_path);
-            println!("📁 文件是否存在: {}", cookie_path.exists());
+            eprintln!("📁 Cookie 文件路径: {:?}", cookie_path);
+            eprintln!("📁 文件是否存在: {}", cookie_path.exists());
 
             if cookie_path.exists() {
                 match CookieStore::load_from_file(&cookie_path) {
                     Ok(store) => {
-                        println!("✅ 成功加载 {} 个已保存的用户 Cookie", store.cookies.len());
+                        eprintln!("✅ 成功加载 {} 个已保存的用户 Cookie", store.cookies.len());
                         let cookie_str = store.to_cookie_string();
-                        println!("🍪 Cookie 内容预览: {}...", &cookie_str.chars().take(100).collect::<String>());
+                        eprintln!("🍪 Cookie 内容预览: {}...", &cookie_str.chars().take(100).collect::<String>());
                         Some(cookie_str)
                     }
                     Err(e) => {
-                        println!("⚠️ 加载保存的 Cookie 失败: {}", e);
+                        eprintln!("⚠️ 加载保存的 Cookie 失败: {}", e);
                         None
                     }
                 }
             } else {
-                println!("ℹ️ 未找到保存的 Cookie 文件: {:?}", cookie_path);
-                println!("💡 如果您已登录过，请检查文件是否被删除");
+                eprintln!("ℹ️ 未找到保存的 Cookie 文件: {:?}", cookie_path);
+                eprintln!("💡 如果您已登录过，请检查文件是否被删除");
                 None
             }
         } else {
-            println!("❌ 无法获取 Cookie 文件路径");
+            eprintln!("❌ 无法获取 Cookie 文件路径");
             None
         };
 
@@ -111,26 +113,26 @@ impl DouYinReq {
         let using_saved_cookies = saved_cookies.is_some();
         if let Some(ref cookie_str) = saved_cookies {
             headers.insert("cookie", cookie_str.parse()?);
-            println!("✓ 已将保存的 Cookie 添加到请求头");
-            println!("📋 Cookie 详情（前200字符）: {}...", &cookie_str.chars().take(200).collect::<String>());
+            eprintln!("✓ 已将保存的 Cookie 添加到请求头");
+            eprintln!("📋 Cookie 详情（前200字符）: {}...", &cookie_str.chars().take(200).collect::<String>());
         } else {
-            println!("ℹ️  未使用保存的 Cookie，仅使用从主页获取的临时 Cookie");
+            eprintln!("ℹ️  未使用保存的 Cookie，仅使用从主页获取的临时 Cookie");
         }
 
-        println!("🌐 开始发送请求到直播间页面...");
+        eprintln!("🌐 开始发送请求到直播间页面...");
         let request = self.request.get(self.room_url.clone()).headers(headers);
         let response = request.send().await?;
 
         // 记录响应状态
         let status = response.status();
-        println!("📊 响应状态码: {}", status);
+        eprintln!("📊 响应状态码: {}", status);
 
         // 先使用cookie，再使用text
         let cookies = response.cookies();
         let mut ttwid = String::new();
-        println!("🍪 从响应中获取的 Cookie:");
+        eprintln!("🍪 从响应中获取的 Cookie:");
         for c in cookies {
-            println!("   - {}: {} (domain: {:?}, path: {:?})",
+            eprintln!("   - {}: {} (domain: {:?}, path: {:?})",
                 c.name(),
                 if c.value().len() > 50 { format!("{}...", &c.value()[..50]) } else { c.value().to_string() },
                 c.domain(),
@@ -142,23 +144,23 @@ impl DouYinReq {
         }
 
         // 获取cookie里面的ttwid
-        println!("📄 开始读取响应内容...");
+        eprintln!("📄 开始读取响应内容...");
         let body = response.text().await?;
-        println!("📏 响应内容长度: {} 字符", body.len());
+        eprintln!("📏 响应内容长度: {} 字符", body.len());
 
         // 显示响应内容的开头和结尾（用于调试）
-        if body.len() > 0 {
+        if !body.is_empty() {
             let preview_start = body.chars().take(500).collect::<String>();
             let preview_end = if body.len() > 500 {
                 body.chars().skip(body.len().saturating_sub(300)).collect::<String>()
             } else {
                 String::new()
             };
-            println!("📄 响应内容预览（前500字符）:");
-            println!("{}", preview_start);
+            eprintln!("📄 响应内容预览（前500字符）:");
+            eprintln!("{}", preview_start);
             if !preview_end.is_empty() {
-                println!("📄 响应内容预览（最后300字符）:");
-                println!("{}", preview_end);
+                eprintln!("📄 响应内容预览（最后300字符）:");
+                eprintln!("{}", preview_end);
             }
         }
 
@@ -180,14 +182,14 @@ impl DouYinReq {
         }
 
         if let Some(reason) = deny_reason {
-            println!("\n❌ ========== 访问被拒绝 ==========");
-            println!("❌ 检测到需要{}验证", if is_captcha { "验证码" } else { "登录或" });
-            println!("📍 拒绝原因: {}", reason);
-            println!("🍪 是否使用了保存的 Cookie: {}", if using_saved_cookies { "是" } else { "否" });
+            eprintln!("\n❌ ========== 访问被拒绝 ==========");
+            eprintln!("❌ 检测到需要{}验证", if is_captcha { "验证码" } else { "登录或" });
+            eprintln!("📍 拒绝原因: {}", reason);
+            eprintln!("🍪 是否使用了保存的 Cookie: {}", if using_saved_cookies { "是" } else { "否" });
             if using_saved_cookies {
                 if let Some(ref cookie_str) = saved_cookies {
-                    println!("📋 使用的 Cookie 数量: {} 个", cookie_str.split(';').count());
-                    println!("📋 Cookie 示例:");
+                    eprintln!("📋 使用的 Cookie 数量: {} 个", cookie_str.split(';').count());
+                    eprintln!("📋 Cookie 示例:");
                     for (i, cookie) in cookie_str.split(';').take(5).enumerate() {
                         let parts: Vec<&str> = cookie.trim().splitn(2, '=').collect();
                         if parts.len() == 2 {
@@ -196,39 +198,43 @@ impl DouYinReq {
                             } else {
                                 parts[1].to_string()
                             };
-                            println!("   {}. {} = {}", i + 1, parts[0], value_preview);
+                            eprintln!("   {}. {} = {}", i + 1, parts[0], value_preview);
                         }
                     }
                 }
             }
-            println!("🌐 请求的 URL: {}", self.room_url);
+            eprintln!("🌐 请求的 URL: {}", self.room_url);
 
             if is_captcha {
-                println!("💡 提示: 需要打开浏览器窗口让用户完成验证码验证");
-                println!("======================================\n");
-                return Err(crate::command::model::ERROR_CAPTCHA_REQUIRED.into());
+                eprintln!("💡 提示: 需要打开浏览器窗口让用户完成验证码验证");
+                eprintln!("======================================\n");
+                return Err(crate::model::ERROR_CAPTCHA_REQUIRED.into());
             } else {
-                println!("💡 提示: 后端将根据 Cookie 文件是否存在决定是否打开登录窗口");
-                println!("======================================\n");
-                return Err(crate::command::model::ERROR_ACCESS_DENIED.into());
+                eprintln!("💡 提示: 后端将根据 Cookie 文件是否存在决定是否打开登录窗口");
+                eprintln!("======================================\n");
+                return Err(crate::model::ERROR_ACCESS_DENIED.into());
             }
         }
 
-        // println!("获取的直播间HTML内容是：{}", body);
-        // 判断是不是已经停播了，是的话仅获取主播头像
-        // 使用正则表达式匹配直播间信息
-        let re;
+        // 解析页面里的 roomStore.roomInfo
+        let Some(mut page) = parse_room_page(&body) else {
+            eprintln!("❌ 无法解析房间信息，可能需要登录或页面结构已变化");
+            eprintln!("💡 提示: 后端将自动打开登录窗口");
+            return Err(crate::model::ERROR_ACCESS_DENIED.into());
+        };
+        if page.room.web_rid.is_empty() {
+            if let Ok(RoomLink::WebRid(web_rid)) = parse_room_input(&self.room_url) {
+                page.room.web_rid = web_rid;
+            }
+        }
+
+        let status = page.room.room_status();
         let mut unique_id = "";
-        if body.contains(r#"status\":4"#) {
-            println!("主播已停播了");
-            // 使用正则表达式匹配直播间信息
-            re = Regex::new(r#"anchor\\":(.*?),\\"open_id_str"#).unwrap();
+        if !status.is_live() {
+            eprintln!("主播没有在直播: {}", status.label());
         } else {
-            // 使用正则表达式匹配直播间信息
-            re = Regex::new(r#"roomInfo\\":\{\\"room\\":(.*?),\\"toolbar_data"#).unwrap();
-
             // 尝试多种正则模式来匹配 user_unique_id
-            let patterns = vec![
+            let patterns = [
                 r#"user_unique_id\\":\\"(.*?)\\"}"#,           // 原始模式
                 r#"user_unique_id":"([^"]+)"#,                 // 不带转义的模式
                 r#"user_unique_id\\":\\"([^\\]+)\\"#,         // 更宽松的模式
@@ -242,7 +248,7 @@ impl DouYinReq {
                     if let Some(captures) = unique_re.captures(&body) {
                         if let Some(m) = captures.get(1) {
                             unique_id = m.as_str();
-                            println!("✓ 成功提取 unique_id: {} (使用模式: {})", unique_id, pattern);
+                            eprintln!("✓ 成功提取 unique_id: {} (使用模式: {})", unique_id, pattern);
                             matched = true;
                             break;
                         }
@@ -251,40 +257,26 @@ impl DouYinReq {
             }
 
             if !matched {
-                println!("⚠ 警告: 所有正则模式都无法匹配 user_unique_id");
-                println!("  这可能是因为:");
-                println!("  1. 页面结构已变化");
-                println!("  2. 需要登录才能访问");
-                println!("  3. 直播间不存在或已关闭");
+                eprintln!("⚠ 警告: 所有正则模式都无法匹配 user_unique_id");
+                eprintln!("  这可能是因为:");
+                eprintln!("  1. 页面结构已变化");
+                eprintln!("  2. 需要登录才能访问");
+                eprintln!("  3. 直播间不存在或已关闭");
 
                 // 输出部分 body 内容用于调试（仅前 500 字符，避免输出过多）
                 let preview_len = 500.min(body.len());
-                println!("  HTML 预览 (前 {} 字符):", preview_len);
-                println!("  {}", &body[..preview_len]);
+                eprintln!("  HTML 预览 (前 {} 字符):", preview_len);
+                eprintln!("  {}", &body[..preview_len]);
             }
         }
 
-        // 安全地获取房间信息
-        let main_info = match re.captures(&body) {
-            Some(captures) => match captures.get(1) {
-                Some(matched) => matched.as_str(),
-                None => {
-                    println!("❌ 无法提取房间信息，可能需要登录");
-                    return Err(crate::command::model::ERROR_ACCESS_DENIED.into());
-                }
-            },
-            None => {
-                println!("❌ 无法匹配房间信息，可能需要登录或页面结构已变化");
-                println!("💡 提示: 后端将自动打开登录窗口");
-                return Err(crate::command::model::ERROR_ACCESS_DENIED.into());
-            }
-        };
-        // 替换里面的双引号,方便json解析
-        let room_info = String::from(main_info) + "}";
-        self.room_info = room_info.replace(r#"\""#, r#"""#);
-        // println!("直播间信息是：{}", self.room_info);
+        eprintln!("✓ 直播间: {} ({})", page.room.title, page.room.room_id);
+        self.room_info = page.raw;
         Ok(LiveInfo {
             room_info: self.room_info.clone(),
+            streams: page.room.stream_url.sources(),
+            room: page.room,
+            status,
             ttwid,
             unique_id: String::from(unique_id),
         })
@@ -312,7 +304,7 @@ impl DouYinReq {
     //     let request = self.request.get(format!("https://live.douyin.com/webcast/ranklist/audience/?aid=6383&app_name=douyin_web&live_id=1&device_platform=web&language=zh-CN&cookie_enabled=true&screen_width=2560&screen_height=1440&browser_language=zh-CN&browser_platform=Win32&browser_name=Chrome&browser_version=117.0.0.0&webcast_sdk_version=2450&room_info={}&rank_type=30", self.room_info)).headers(headers);
     //     let response = request.send().await?;
     //     let json_value = response.text().await?;
-    //     println!("rank json value:{json_value:?}");
+    //     eprintln!("rank json value:{json_value:?}");
     //     Ok(())
     // }
 }
@@ -0,0 +1,285 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use super::overlay;
+use crate::model::{LiveEvent, LiveEventPayload};
+
+// 默认监听端口
+pub const DEFAULT_PORT: u16 = 5100;
+
+// 订阅者跟不上时最多缓存的消息数，超过后丢弃最早的消息
+const CHANNEL_CAPACITY: usize = 1024;
+
+// 连接后等待请求头的最长时间，超时不发请求的连接直接断开
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 连接的订阅条件，topics 是事件类型（chat/gift/like/member/follow...），rooms 是房间号，为空表示全部
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Subscription {
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub rooms: Vec<String>,
+}
+
+impl Subscription {
+    /// 从连接地址的查询参数解析，例如 ws://127.0.0.1:5100/?topics=chat,gift&rooms=7376...
+    pub fn from_query(query: &str) -> Self {
+        let mut subscription = Subscription::default();
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = urlencoding::decode(value).map(|v| v.to_string()).unwrap_or_default();
+            let values = value
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+            match key {
+                "topics" | "topic" => subscription.topics.extend(values),
+                "rooms" | "room" => subscription.rooms.extend(values),
+                _ => {}
+            }
+        }
+        subscription
+    }
+
+    pub fn matches(&self, room_id: &str, kind: &str) -> bool {
+        (self.topics.is_empty() || self.topics.iter().any(|t| t == kind))
+            && (self.rooms.is_empty() || self.rooms.iter().any(|r| r == room_id))
+    }
+}
+
+// 序列化好的一条消息，所有连接共用
+#[derive(Debug)]
+struct Frame {
+    room_id: String,
+    kind: &'static str,
+    text: String,
+}
+
+// 本地广播服务，只监听 127.0.0.1，drop 时停止
+// 同一个端口上 WebSocket 连接订阅事件，普通 HTTP 请求返回浮层页面（/overlay/chat 等）
+pub struct BroadcastServer {
+    addr: SocketAddr,
+    sender: broadcast::Sender<Arc<Frame>>,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl BroadcastServer {
+    /// 在本机的 port 端口启动服务，port 为 0 时由系统分配
+    pub async fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+        let addr = listener.local_addr()?;
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        tokio::spawn(accept_loop(listener, sender.clone(), stop_rx));
+        eprintln!("📡 [广播] 本地推送服务已启动: ws://{0}，浮层页面: http://{0}/overlay", addr);
+        Ok(BroadcastServer {
+            addr,
+            sender,
+            stop: Some(stop_tx),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 当前连接数
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// 把一条事件发给所有订阅了这个房间和类型的连接
+    pub fn publish(&self, room_id: &str, event: &LiveEvent) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let kind = event.kind();
+        let message = LiveEventPayload::new(room_id, event.clone());
+        match serde_json::to_string(&message) {
+            Ok(text) => {
+                let _ = self.sender.send(Arc::new(Frame {
+                    room_id: room_id.to_string(),
+                    kind,
+                    text,
+                }));
+            }
+            Err(e) => eprintln!("⚠️ [广播] 序列化失败: {}", e),
+        }
+    }
+
+    /// 停止服务，已经建立的连接会在 drop 后断开
+    pub fn close(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+            eprintln!("📡 [广播] 本地推送服务已停止: ws://{}", self.addr);
+        }
+    }
+}
+
+impl Drop for BroadcastServer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    sender: broadcast::Sender<Arc<Frame>>,
+    mut stop: oneshot::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    let sender = sender.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, sender).await {
+                            eprintln!("⚠️ [广播] 连接 {} 出错: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => eprintln!("⚠️ [广播] 接受连接失败: {}", e),
+            }
+        }
+    }
+}
+
+// 根据请求头分流：WebSocket 握手交给订阅处理，其它请求返回浮层页面
+async fn handle_connection(
+    stream: TcpStream,
+    sender: broadcast::Sender<Arc<Frame>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let head = tokio::time::timeout(HEAD_TIMEOUT, overlay::peek_head(&stream))
+        .await
+        .map_err(|_| "等待请求头超时")??;
+    if head.is_websocket() {
+        // 只有 WebSocket 连接才订阅消息，页面请求不算在连接数里
+        serve_connection(stream, sender.subscribe()).await
+    } else {
+        Ok(overlay::serve_page(stream, head).await?)
+    }
+}
+
+// 处理一个订阅者：按订阅条件转发消息，收到文本消息时更新订阅条件
+// 握手回调的返回类型由 tungstenite 决定，错误类型比较大
+#[allow(clippy::result_large_err)]
+async fn serve_connection(
+    stream: TcpStream,
+    mut receiver: broadcast::Receiver<Arc<Frame>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut query = String::new();
+    let ws = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+        query = request.uri().query().unwrap_or("").to_string();
+        Ok(response)
+    })
+    .await?;
+    let mut subscription = Subscription::from_query(&query);
+    let (mut write, mut read) = ws.split();
+
+    loop {
+        tokio::select! {
+            frame = receiver.recv() => match frame {
+                Ok(frame) => {
+                    if subscription.matches(&frame.room_id, frame.kind) {
+                        write.send(WsMessage::Text(frame.text.clone())).await?;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    eprintln!("⚠️ [广播] 订阅者处理太慢，丢弃了 {} 条消息", count);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = read.next() => match message {
+                // 发送 {"topics": [...], "rooms": [...]} 修改订阅条件
+                Some(Ok(WsMessage::Text(text))) => match serde_json::from_str::<Subscription>(&text) {
+                    Ok(update) => {
+                        subscription = update;
+                        let ack = serde_json::json!({ "subscribed": &subscription });
+                        write.send(WsMessage::Text(ack.to_string())).await?;
+                    }
+                    Err(e) => {
+                        let error = serde_json::json!({ "error": e.to_string() });
+                        write.send(WsMessage::Text(error.to_string())).await?;
+                    }
+                },
+                Some(Ok(WsMessage::Ping(data))) => write.send(WsMessage::Pong(data)).await?,
+                Some(Ok(WsMessage::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_everything() {
+        let subscription = Subscription::from_query("");
+        assert!(subscription.topics.is_empty());
+        assert!(subscription.rooms.is_empty());
+        assert!(subscription.matches("1", "chat"));
+        assert!(subscription.matches("2", "gift"));
+    }
+
+    #[test]
+    fn topics_filter_kinds() {
+        let subscription = Subscription::from_query("topics=chat,gift");
+        assert_eq!(subscription.topics, ["chat", "gift"]);
+        assert!(subscription.matches("1", "chat"));
+        assert!(subscription.matches("1", "gift"));
+        assert!(!subscription.matches("1", "like"));
+
+        // 单数写法和多次出现的参数都认
+        let subscription = Subscription::from_query("topic=chat&topic=like");
+        assert_eq!(subscription.topics, ["chat", "like"]);
+    }
+
+    #[test]
+    fn unknown_topics_and_keys() {
+        // 不认识的类型照样保留，只是没有事件能匹配上
+        let subscription = Subscription::from_query("topics=nope");
+        assert_eq!(subscription.topics, ["nope"]);
+        assert!(!subscription.matches("1", "chat"));
+
+        // 不认识的参数忽略
+        let subscription = Subscription::from_query("token=abc&foo");
+        assert!(subscription.topics.is_empty());
+        assert!(subscription.rooms.is_empty());
+        assert!(subscription.matches("1", "chat"));
+    }
+
+    #[test]
+    fn rooms_filter_room_ids() {
+        let subscription = Subscription::from_query("room=7376&topics=gift");
+        assert_eq!(subscription.rooms, ["7376"]);
+        assert!(subscription.matches("7376", "gift"));
+        assert!(!subscription.matches("7377", "gift"));
+        assert!(!subscription.matches("7376", "chat"));
+
+        // 空值等于不限制
+        let subscription = Subscription::from_query("room=&rooms=,");
+        assert!(subscription.rooms.is_empty());
+        assert!(subscription.matches("7377", "chat"));
+    }
+
+    #[test]
+    fn decodes_url_encoded_values() {
+        let subscription = Subscription::from_query("topics=chat%2Cgift&rooms=%207376%20,7377");
+        assert_eq!(subscription.topics, ["chat", "gift"]);
+        assert_eq!(subscription.rooms, ["7376", "7377"]);
+        assert!(subscription.matches("7376", "gift"));
+    }
+}
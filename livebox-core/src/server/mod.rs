@@ -0,0 +1,3 @@
+// 本地服务：给 OBS 浏览器源、看板等外部工具提供实时消息和浮层页面
+pub mod broadcast;
+mod overlay;
@@ -0,0 +1,111 @@
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// 浮层页面直接打包进程序里，不需要额外的静态文件目录
+const PAGES: &[(&str, &str, &str)] = &[
+    ("/", "text/html", include_str!("overlay/index.html")),
+    ("/overlay", "text/html", include_str!("overlay/index.html")),
+    ("/overlay/chat", "text/html", include_str!("overlay/chat.html")),
+    ("/overlay/gift", "text/html", include_str!("overlay/gift.html")),
+    ("/overlay/like", "text/html", include_str!("overlay/like.html")),
+    ("/overlay/goal", "text/html", include_str!("overlay/goal.html")),
+    ("/overlay/common.js", "application/javascript", include_str!("overlay/common.js")),
+];
+
+// 请求头最大长度
+const MAX_HEAD_LEN: usize = 8 * 1024;
+
+// 已经读到的请求头
+pub(crate) struct RequestHead {
+    text: String,
+    // 请求头在连接里占的字节数，包括结尾的空行；text 里的无效 UTF-8 会被替换，长度不一定相同
+    len: usize,
+}
+
+impl RequestHead {
+    /// 请求行里的方法和路径（不含查询参数）
+    fn method_and_path(&self) -> (&str, &str) {
+        let mut parts = self.text.lines().next().unwrap_or("").split_whitespace();
+        let method = parts.next().unwrap_or("");
+        let target = parts.next().unwrap_or("/");
+        let path = target.split('?').next().unwrap_or("/");
+        (method, path)
+    }
+
+    /// 是否是 WebSocket 握手请求
+    pub(crate) fn is_websocket(&self) -> bool {
+        self.text.lines().skip(1).any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("upgrade")
+                    && value.trim().eq_ignore_ascii_case("websocket")
+            })
+        })
+    }
+}
+
+/// 先偷看请求头，不从连接里读走数据，判断是 WebSocket 还是普通 HTTP 请求
+pub(crate) async fn peek_head(stream: &TcpStream) -> std::io::Result<RequestHead> {
+    let mut buf = vec![0u8; MAX_HEAD_LEN];
+    // 请求头一般一次就能收完，没收完时稍等一下再看
+    for _ in 0..50 {
+        let len = stream.peek(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        if let Some(end) = find_head_end(&buf[..len]) {
+            return Ok(RequestHead {
+                text: String::from_utf8_lossy(&buf[..end]).to_string(),
+                len: end,
+            });
+        }
+        if len == buf.len() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "请求头不完整",
+    ))
+}
+
+/// 返回浮层页面
+pub(crate) async fn serve_page(mut stream: TcpStream, head: RequestHead) -> std::io::Result<()> {
+    // 把请求头读走，避免还没读的数据导致连接被重置
+    let mut consumed = vec![0u8; head.len];
+    stream.read_exact(&mut consumed).await?;
+
+    let (method, path) = head.method_and_path();
+    let path = match path.trim_end_matches('/') {
+        "" => "/",
+        path => path,
+    };
+    let response = match (method, PAGES.iter().find(|(p, _, _)| *p == path)) {
+        ("GET" | "HEAD", Some((_, content_type, body))) => {
+            response("200 OK", content_type, body, method == "HEAD")
+        }
+        ("GET" | "HEAD", None) => response("404 Not Found", "text/plain", "Not Found", false),
+        _ => response("405 Method Not Allowed", "text/plain", "Method Not Allowed", false),
+    };
+    stream.write_all(&response).await?;
+    stream.shutdown().await
+}
+
+fn response(status: &str, content_type: &str, body: &str, head_only: bool) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    if !head_only {
+        response.extend_from_slice(body.as_bytes());
+    }
+    response
+}
+
+fn find_head_end(data: &[u8]) -> Option<usize> {
+    data.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
+}
@@ -0,0 +1,42 @@
+// LiveBox 浮层页面公用脚本：读取地址参数里的主题设置，连接本地推送服务
+const params = new URLSearchParams(location.search)
+
+// 读取地址参数，没有设置时使用默认值
+function param(name, fallback) {
+    const value = params.get(name)
+    return value === null || value === '' ? fallback : value
+}
+
+// 颜色参数可以省略 #，例如 color=ffffff
+function colorParam(name, fallback) {
+    const value = param(name, fallback)
+    return /^[0-9a-fA-F]{3,8}$/.test(value) ? '#' + value : value
+}
+
+// 把主题参数设置到 CSS 变量上：color 文字颜色、bg 背景、accent 强调色、size 字号、font 字体
+function applyTheme() {
+    const root = document.documentElement.style
+    root.setProperty('--color', colorParam('color', '#ffffff'))
+    root.setProperty('--bg', colorParam('bg', 'transparent'))
+    root.setProperty('--accent', colorParam('accent', '#ffcc33'))
+    root.setProperty('--size', param('size', '20') + 'px')
+    root.setProperty('--font', param('font', 'sans-serif'))
+}
+
+// 订阅指定类型的事件，room 参数可以只看某个直播间，断开后自动重连
+function connectEvents(topics, onEvent) {
+    const query = new URLSearchParams({ topics: topics.join(',') })
+    if (param('room', '')) {
+        query.set('rooms', param('room', ''))
+    }
+    const ws = new WebSocket(`ws://${location.host}/?${query}`)
+    ws.onmessage = (message) => {
+        const data = JSON.parse(message.data)
+        if (data.event) {
+            onEvent(data.event, data.room_id)
+        }
+    }
+    ws.onclose = () => setTimeout(() => connectEvents(topics, onEvent), 2000)
+}
+
+applyTheme()
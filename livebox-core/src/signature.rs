@@ -0,0 +1,186 @@
+use rand::Rng;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// webmssdk.js 里 frontierSign 使用的自定义 base64 字符表（无补位）
+const BOGUS_ALPHABET: &[u8] = b"Dkdpgh4ZKsQB80/Mfvw36XI1R25+WUAlEi7NLboqYTOPuzmFjJnryx9HVGcaStCe";
+
+// webmssdk.js 中的全局计数器，每签名一次加 1，只取低 6 位
+static BOGUS_INDEX: AtomicU32 = AtomicU32::new(0);
+
+// 浏览器环境相关的参数，对应 webmssdk.js 里的 envcode / ubcode / initialized
+// envcode 在 webmssdk.js 里初始化为 0，只有环境检测不通过时才会置位（在 Node 里运行会变成 0x81），
+// 之前签名都是在 webview 里算的，所以默认用 0；没有鼠标键盘等行为时 ubcode 为 14；没有调用过 init 时 initialized 为 false
+#[derive(Debug, Clone, Copy)]
+pub struct SignEnv {
+    pub envcode: u16,
+    pub ubcode: u8,
+    pub initialized: bool,
+}
+
+impl Default for SignEnv {
+    fn default() -> Self {
+        SignEnv {
+            envcode: 0,
+            ubcode: 14,
+            initialized: false,
+        }
+    }
+}
+
+/// 生成弹幕连接地址里的 signature 参数，和 vFun.js 中 window.creatSignature 的结果一致
+pub fn creat_signature(room_id: &str, unique_id: &str) -> String {
+    let stub = x_ms_stub(room_id, unique_id);
+    frontier_sign(&stub)
+}
+
+/// 参数字符串的 MD5，也就是 frontierSign 需要的 X-MS-STUB
+/// 例如 room_id=7392456584396114739, user_unique_id=7392457010592581183 时为 e022aed3af1dfcf2dd0080faa0c2563c
+pub fn x_ms_stub(room_id: &str, unique_id: &str) -> String {
+    let params = format!(
+        "live_id=1,aid=6383,version_code=180800,webcast_sdk_version=1.0.14-beta.0,room_id={},sub_room_id=,sub_channel_id=,did_rule=3,user_unique_id={},device_platform=web,device_type=,ac=,identity=audience",
+        room_id, unique_id
+    );
+    format!("{:x}", md5::compute(params))
+}
+
+/// 对 X-MS-STUB 签名，返回 X-Bogus，等价于 byted_acrawler.frontierSign({ 'X-MS-STUB': stub })['X-Bogus']
+pub fn frontier_sign(stub: &str) -> String {
+    let index = BOGUS_INDEX.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut rng = rand::thread_rng();
+    // 随机数的使用顺序和 JS 中 Math.random() 的调用顺序一致
+    let random = [rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()];
+    frontier_sign_with(stub, index, random, SignEnv::default())
+}
+
+/// frontierSign 的确定性版本，计数器和随机数都由调用方传入，结果可以和 webmssdk.js 逐位对比
+///
+/// 在 Node 中固定 Math.random 时 webmssdk.js 的输出见下面的测试
+pub fn frontier_sign_with(stub: &str, index: u32, random: [f64; 3], env: SignEnv) -> String {
+    // 第一个字节：固定的 websocket 类型位 + 是否初始化 + 一位随机数
+    let header = (1u8 << 6)
+        | ((env.initialized as u8) << 5)
+        | ((((100.0 * random[0]).floor() as u32 & 1) as u8) << 4);
+
+    // 空字符串 MD5 的原始字节再做一次 MD5，以及 stub 原始字节的 MD5，各取最后两个字节
+    let empty = md5::compute(md5::compute("").0);
+    let stub_bytes = decode_hex(stub).unwrap_or_else(|| vec![0; 16]);
+    let stub_hash = md5::compute(stub_bytes);
+
+    let mut buf = vec![
+        (index & 63) as u8,
+        (env.envcode >> 8) as u8,
+        (env.envcode & 255) as u8,
+        env.ubcode,
+        empty[14],
+        empty[15],
+        stub_hash[14],
+        stub_hash[15],
+        ((255.0 * random[1]).floor() as u32 & 255) as u8,
+    ];
+    // 末尾追加异或校验位
+    let checksum = buf.iter().fold(0u8, |acc, b| acc ^ b);
+    buf.push(checksum);
+
+    // 用一个随机字节作为 RC4 的密钥加密
+    let key = ((255.0 * random[2]).floor() as u32 & 255) as u8;
+    let mut out = vec![header, key];
+    out.extend(rc4(&[key], &buf));
+    encode_bogus(&out)
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j = 0usize;
+    for i in 0..256 {
+        j = (j + s[i] as usize + key[i % key.len()] as usize) % 256;
+        s.swap(i, j);
+    }
+    let (mut i, mut j) = (0usize, 0usize);
+    data.iter()
+        .map(|b| {
+            i = (i + 1) % 256;
+            j = (j + s[i] as usize) % 256;
+            s.swap(i, j);
+            b ^ s[(s[i] as usize + s[j] as usize) % 256]
+        })
+        .collect()
+}
+
+// 按 3 字节一组编码，签名数据固定 12 字节，不需要补位
+fn encode_bogus(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let mut n = 0u32;
+        for (i, b) in chunk.iter().enumerate() {
+            n |= (*b as u32) << (16 - 8 * i);
+        }
+        for i in 0..=chunk.len() {
+            out.push(BOGUS_ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 在 Node 中运行 webmssdk.js，固定 Math.random，ubcode=14、initialized=false、index=1
+    const STUB: &str = "069bd6275204dd05fcf936917710f656";
+
+    #[test]
+    fn x_ms_stub_matches_js() {
+        assert_eq!(
+            x_ms_stub("7392456584396114739", "7392457010592581183"),
+            "e022aed3af1dfcf2dd0080faa0c2563c"
+        );
+    }
+
+    // Node 里环境检测不通过，envcode 是 0x81
+    #[test]
+    fn frontier_sign_matches_js_in_node() {
+        let env = SignEnv {
+            envcode: 0x81,
+            ..SignEnv::default()
+        };
+        assert_eq!(
+            frontier_sign_with(STUB, 1, [0.0, 0.0, 0.0], env),
+            "fDpl4DiMGE70dKYf"
+        );
+        assert_eq!(
+            frontier_sign_with(STUB, 1, [0.0, 0.0, 1.0 / 255.0], env),
+            "fDgZdKSD1veG4rLb"
+        );
+    }
+
+    // 把 webmssdk.js 的环境检测结果固定为 0，也就是默认的浏览器环境
+    #[test]
+    fn frontier_sign_matches_js_in_browser() {
+        let env = SignEnv::default();
+        assert_eq!(
+            frontier_sign_with(STUB, 1, [0.0, 0.0, 0.0], env),
+            "fDpl4KbMGE70dKOv"
+        );
+        assert_eq!(
+            frontier_sign_with(STUB, 1, [0.0, 0.0, 1.0 / 255.0], env),
+            "fDgZdDVD1veG4roL"
+        );
+    }
+
+    #[test]
+    fn signature_uses_alphabet() {
+        let signature = creat_signature("7392456584396114739", "7392457010592581183");
+        assert_eq!(signature.len(), 16);
+        assert!(signature.bytes().all(|b| BOGUS_ALPHABET.contains(&b)));
+    }
+}
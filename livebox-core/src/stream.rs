@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::room::StreamUrl;
+
+// 清晰度，从高到低排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    Origin,
+    Uhd,
+    Hd,
+    Sd,
+    Ld,
+}
+
+impl Quality {
+    /// flv_pull_url 里的 key（ORIGIN、FULL_HD1、HD1、SD2、SD1）或者 SDK 里的 sdk_key
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "ORIGIN" | "origin" => Some(Quality::Origin),
+            "FULL_HD1" | "uhd" => Some(Quality::Uhd),
+            "HD1" | "hd" => Some(Quality::Hd),
+            "SD2" | "sd" => Some(Quality::Sd),
+            "SD1" | "ld" => Some(Quality::Ld),
+            _ => None,
+        }
+    }
+
+    pub fn sdk_key(self) -> &'static str {
+        match self {
+            Quality::Origin => "origin",
+            Quality::Uhd => "uhd",
+            Quality::Hd => "hd",
+            Quality::Sd => "sd",
+            Quality::Ld => "ld",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quality::Origin => "原画",
+            Quality::Uhd => "蓝光",
+            Quality::Hd => "超清",
+            Quality::Sd => "高清",
+            Quality::Ld => "标清",
+        }
+    }
+}
+
+impl FromStr for Quality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Quality::from_key(s)
+            .ok_or_else(|| format!("不支持的清晰度: {}，可选 origin、uhd、hd、sd、ld", s))
+    }
+}
+
+// 一种清晰度的拉流地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamSource {
+    pub quality: Quality,
+    pub name: String,
+    pub resolution: String,
+    pub flv: Option<String>,
+    pub hls: Option<String>,
+}
+
+// 直播间所有清晰度的拉流地址，按清晰度从高到低排列
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StreamSources {
+    // 网页播放器默认播放的清晰度
+    pub default_quality: Option<Quality>,
+    pub sources: Vec<StreamSource>,
+}
+
+impl StreamSources {
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn get(&self, quality: Quality) -> Option<&StreamSource> {
+        self.sources.iter().find(|s| s.quality == quality)
+    }
+
+    /// 最高的清晰度
+    pub fn best(&self) -> Option<&StreamSource> {
+        self.sources.first()
+    }
+
+    /// 指定的清晰度，没有时依次取更低的，都没有时取最高的
+    pub fn pick(&self, quality: Quality) -> Option<&StreamSource> {
+        self.sources
+            .iter()
+            .find(|s| s.quality >= quality)
+            .or_else(|| self.best())
+    }
+}
+
+// stream_data 的结构：{"data": {"origin": {"main": {"flv": "...", "hls": "..."}}, ...}}
+#[derive(Deserialize)]
+struct StreamData {
+    #[serde(default)]
+    data: BTreeMap<String, StreamDataItem>,
+}
+
+#[derive(Deserialize)]
+struct StreamDataItem {
+    #[serde(default)]
+    main: Option<StreamDataMain>,
+}
+
+#[derive(Deserialize, Default)]
+struct StreamDataMain {
+    #[serde(default)]
+    flv: String,
+    #[serde(default)]
+    hls: String,
+}
+
+impl StreamUrl {
+    /// 整理出每种清晰度的 FLV 和 HLS 地址
+    ///
+    /// flv_pull_url / hls_pull_url_map 一般没有原画，原画从 live_core_sdk_data 的 stream_data 里取
+    pub fn sources(&self) -> StreamSources {
+        let mut sources: BTreeMap<Quality, StreamSource> = BTreeMap::new();
+        let mut add = |quality: Quality, flv: &str, hls: &str| {
+            let source = sources.entry(quality).or_insert_with(|| StreamSource {
+                quality,
+                name: quality.label().to_string(),
+                resolution: String::new(),
+                flv: None,
+                hls: None,
+            });
+            if source.flv.is_none() && !flv.is_empty() {
+                source.flv = Some(flv.to_string());
+            }
+            if source.hls.is_none() && !hls.is_empty() {
+                source.hls = Some(hls.to_string());
+            }
+        };
+
+        for (key, url) in &self.flv_pull_url {
+            if let Some(quality) = Quality::from_key(key) {
+                add(quality, url, "");
+            }
+        }
+        for (key, url) in &self.hls_pull_url_map {
+            if let Some(quality) = Quality::from_key(key) {
+                add(quality, "", url);
+            }
+        }
+        let pull_data = &self.live_core_sdk_data.pull_data;
+        if let Ok(stream_data) = serde_json::from_str::<StreamData>(&pull_data.stream_data) {
+            for (key, item) in &stream_data.data {
+                if let (Some(quality), Some(main)) = (Quality::from_key(key), &item.main) {
+                    add(quality, &main.flv, &main.hls);
+                }
+            }
+        }
+        let default_quality = Quality::from_key(&pull_data.options.default_quality.sdk_key)
+            .or_else(|| Quality::from_key(&self.default_resolution));
+        // hls_pull_url 是默认清晰度的 HLS 地址，hls_pull_url_map 为空时用它
+        if !self.hls_pull_url.is_empty() {
+            if let Some(quality) = default_quality {
+                add(quality, "", &self.hls_pull_url);
+            }
+        }
+
+        // 补上 SDK 里的名称和分辨率
+        for option in &pull_data.options.qualities {
+            let Some(quality) = Quality::from_key(&option.sdk_key) else {
+                continue;
+            };
+            if let Some(source) = sources.get_mut(&quality) {
+                if !option.name.is_empty() {
+                    source.name = option.name.clone();
+                }
+                source.resolution = option.resolution.clone();
+            }
+        }
+
+        StreamSources {
+            default_quality: default_quality.filter(|q| sources.contains_key(q)),
+            sources: sources.into_values().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::RoomInfo;
+
+    const ROOM: &str = include_str!("../../analysis/roomInfo.json");
+
+    #[test]
+    fn sources_from_fixture() {
+        let room = RoomInfo::from_json(ROOM).unwrap();
+        let sources = room.stream_url.sources();
+        let qualities: Vec<Quality> = sources.sources.iter().map(|s| s.quality).collect();
+        assert_eq!(
+            qualities,
+            [Quality::Uhd, Quality::Hd, Quality::Sd, Quality::Ld]
+        );
+        assert_eq!(sources.default_quality, Some(Quality::Hd));
+
+        let flv = |quality| sources.get(quality).and_then(|s| s.flv.as_deref()).unwrap();
+        let hd = sources.get(Quality::Hd).unwrap();
+        assert_eq!(hd.name, "超清");
+        assert_eq!(hd.resolution, "960x720");
+        assert!(flv(Quality::Hd).ends_with("_exphd.flv"));
+        assert!(hd.hls.as_deref().unwrap().ends_with("_exphd/index.m3u8"));
+        assert!(flv(Quality::Sd).ends_with("_expsd.flv"));
+        assert!(flv(Quality::Ld).ends_with("_expld.flv"));
+
+        // 没有原画时取最高的蓝光
+        assert_eq!(sources.pick(Quality::Origin).unwrap().quality, Quality::Uhd);
+        assert_eq!(sources.pick(Quality::Sd).unwrap().quality, Quality::Sd);
+    }
+
+    #[test]
+    fn origin_from_stream_data() {
+        let stream_data = serde_json::json!({
+            "data": {
+                "origin": {"main": {"flv": "http://a/origin.flv", "hls": "http://a/origin.m3u8"}},
+                "ld": {"main": {"flv": "http://a/ld.flv", "hls": ""}},
+            }
+        });
+        let json = serde_json::json!({
+            "default_resolution": "SD1",
+            "flv_pull_url": {"SD1": "http://b/ld.flv"},
+            "live_core_sdk_data": {"pull_data": {"stream_data": stream_data.to_string()}},
+        });
+        let stream: StreamUrl = serde_json::from_value(json).unwrap();
+        let sources = stream.sources();
+        assert_eq!(sources.best().unwrap().quality, Quality::Origin);
+        assert_eq!(
+            sources.get(Quality::Origin).unwrap().hls.as_deref(),
+            Some("http://a/origin.m3u8")
+        );
+        // flv_pull_url 里有的优先
+        let ld = sources.get(Quality::Ld).unwrap();
+        assert_eq!(ld.flv.as_deref(), Some("http://b/ld.flv"));
+        assert_eq!(ld.hls, None);
+        assert_eq!(sources.default_quality, Some(Quality::Ld));
+        assert_eq!(sources.pick(Quality::Hd).unwrap().quality, Quality::Ld);
+    }
+}
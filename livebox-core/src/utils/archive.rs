@@ -0,0 +1,697 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::model::LiveEvent;
+use crate::proto;
+use crate::utils::time::now_millis;
+use crate::utils::paths::livebox_dir;
+
+// 一次直播采集的记录，每次 get_live_html 成功都会新建一条
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub id: i64,
+    pub room_id: String,
+    pub url: String,
+    pub title: String,
+    pub anchor_name: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub event_count: i64,
+    // 重连时可能漏掉消息的次数
+    pub gap_count: i64,
+}
+
+// 会话中可能漏掉消息的一段时间，毫秒时间戳
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionGap {
+    pub id: i64,
+    pub session_id: i64,
+    pub from: u64,
+    pub to: u64,
+    pub reason: String,
+}
+
+// 保存下来的一条消息
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedEvent {
+    pub id: i64,
+    pub session_id: i64,
+    pub room_id: String,
+    pub msg_id: String,
+    pub method: String,
+    pub event_type: String,
+    pub user_id: String,
+    pub nickname: String,
+    pub content: Option<String>,
+    pub received_at: u64,
+    // LiveEvent 序列化后的 JSON
+    pub data: serde_json::Value,
+}
+
+// 分页查询的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPage {
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+    pub events: Vec<ArchivedEvent>,
+}
+
+// 聊天记录搜索条件，时间为毫秒时间戳
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChatSearch {
+    // 搜索弹幕内容和昵称，为空时只按其它条件筛选
+    pub keyword: String,
+    pub room_id: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    // 用户 id 或昵称
+    pub user: Option<String>,
+    pub page: u32,
+    pub page_size: u32,
+    // 每条结果前后各带几条同一会话的聊天消息
+    pub context: u32,
+}
+
+// 一条搜索结果和它前后的聊天消息
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatHit {
+    pub before: Vec<ArchivedEvent>,
+    pub event: ArchivedEvent,
+    pub after: Vec<ArchivedEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatSearchPage {
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+    pub hits: Vec<ChatHit>,
+}
+
+// 消息存档，保存在 ~/.livebox/archive.db
+pub struct Archive {
+    conn: Mutex<Connection>,
+    // 每个房间当前正在写入的会话
+    current: Mutex<HashMap<String, i64>>,
+}
+
+impl Archive {
+    /// 打开默认位置的数据库
+    pub fn open_default() -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = livebox_dir();
+        std::fs::create_dir_all(&dir)?;
+        Self::open(&dir.join("archive.db"))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                room_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                anchor_name TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL REFERENCES sessions(id),
+                room_id TEXT NOT NULL,
+                msg_id TEXT NOT NULL,
+                method TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                nickname TEXT NOT NULL,
+                content TEXT,
+                received_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS gaps (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL REFERENCES sessions(id),
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER NOT NULL,
+                reason TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_sessions_room ON sessions(room_id, started_at);
+            CREATE INDEX IF NOT EXISTS idx_events_session ON events(session_id, id);",
+        )?;
+        create_chat_index(&conn)?;
+        Ok(Archive {
+            conn: Mutex::new(conn),
+            current: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 新建一次采集会话，之后这个房间的消息都记到这个会话下
+    pub fn begin_session(
+        &self,
+        room_id: &str,
+        url: &str,
+        title: &str,
+        anchor_name: &str,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let id = {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            conn.execute(
+                "INSERT INTO sessions (room_id, url, title, anchor_name, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![room_id, url, title, anchor_name, now_millis() as i64],
+            )?;
+            conn.last_insert_rowid()
+        };
+        self.current
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(room_id.to_string(), id);
+        eprintln!("🗄️ [存档] 房间 {} 新建会话 {}", room_id, id);
+        Ok(id)
+    }
+
+    /// 记录会话结束时间，连接断开时调用；之后这个房间再收到消息会新建会话
+    pub fn end_session(&self, room_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(id) = self.current_session(room_id)? else {
+            return Ok(());
+        };
+        self.current
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(room_id);
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE sessions SET ended_at = ?1 WHERE id = ?2",
+            params![now_millis() as i64, id],
+        )?;
+        Ok(())
+    }
+
+    /// 房间当前的会话；这次运行没有新建过会话时，沿用数据库里这个房间最近一个还没结束的会话
+    fn current_session(&self, room_id: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        if let Some(id) = self.current.lock().map_err(|e| e.to_string())?.get(room_id) {
+            return Ok(Some(*id));
+        }
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let id = conn
+            .query_row(
+                "SELECT id FROM sessions WHERE room_id = ?1 AND ended_at IS NULL ORDER BY id DESC LIMIT 1",
+                params![room_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// 保存一条解码后的消息
+    pub fn record(
+        &self,
+        room_id: &str,
+        message: &proto::Message,
+        event: &LiveEvent,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let session_id = match self.current_session(room_id)? {
+            Some(id) => id,
+            None => self.begin_session(room_id, "", "", "")?,
+        };
+        let (user_id, nickname) = event
+            .user()
+            .map(|u| (u.id.clone(), u.nick_name.clone()))
+            .unwrap_or_default();
+        let content = event.content();
+        // 用事件 Common 里的 msgId，和推送、导出里的消息 id 一致；没有解码的消息只能用外层的
+        let msg_id = event
+            .msg_id()
+            .map(String::from)
+            .unwrap_or_else(|| message.msg_id.to_string());
+        let data = serde_json::to_string(event)?;
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        // 消息和全文索引在同一个事务里写入，不会出现搜不到的消息
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO events (session_id, room_id, msg_id, method, event_type, user_id, nickname, content, received_at, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                session_id,
+                room_id,
+                msg_id,
+                message.method,
+                event.kind(),
+                user_id,
+                nickname,
+                content,
+                now_millis() as i64,
+                data,
+            ],
+        )?;
+        // 聊天消息同时写入全文索引
+        if let LiveEvent::Chat { .. } = event {
+            tx.execute(
+                "INSERT INTO chat_fts (rowid, content, nickname) VALUES (?1, ?2, ?3)",
+                params![tx.last_insert_rowid(), content, nickname],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 记录一段可能漏掉消息的时间，重连后没能确认消息连续时调用
+    pub fn record_gap(
+        &self,
+        room_id: &str,
+        from: u64,
+        to: u64,
+        reason: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.current_session(room_id)? else {
+            return Ok(());
+        };
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO gaps (session_id, started_at, ended_at, reason) VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, from as i64, to as i64, reason],
+        )?;
+        Ok(())
+    }
+
+    /// 某个会话里可能漏掉消息的时间段，按时间排序
+    pub fn session_gaps(&self, session_id: i64) -> Result<Vec<SessionGap>, Box<dyn std::error::Error>> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, started_at, ended_at, reason FROM gaps
+             WHERE session_id = ?1 ORDER BY started_at",
+        )?;
+        let gaps = stmt
+            .query_map(params![session_id], |row| {
+                Ok(SessionGap {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    from: row.get::<_, i64>(2)? as u64,
+                    to: row.get::<_, i64>(3)? as u64,
+                    reason: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(gaps)
+    }
+
+    /// 会话列表，按开始时间倒序，可以只看某个房间
+    pub fn list_sessions(
+        &self,
+        room_id: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Session>, Box<dyn std::error::Error>> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.room_id, s.url, s.title, s.anchor_name, s.started_at, s.ended_at,
+                    (SELECT COUNT(*) FROM events e WHERE e.session_id = s.id),
+                    (SELECT COUNT(*) FROM gaps g WHERE g.session_id = s.id)
+             FROM sessions s
+             WHERE ?1 IS NULL OR s.room_id = ?1
+             ORDER BY s.id DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let sessions = stmt
+            .query_map(params![room_id, limit, offset], |row| {
+                Ok(Session {
+                    id: row.get(0)?,
+                    room_id: row.get(1)?,
+                    url: row.get(2)?,
+                    title: row.get(3)?,
+                    anchor_name: row.get(4)?,
+                    started_at: row.get::<_, i64>(5)? as u64,
+                    ended_at: row.get::<_, Option<i64>>(6)?.map(|t| t as u64),
+                    event_count: row.get(7)?,
+                    gap_count: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    /// 分页查看某个会话的消息，page 从 1 开始，可以按事件类型过滤
+    pub fn session_events(
+        &self,
+        session_id: i64,
+        event_type: Option<&str>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<EventPage, Box<dyn std::error::Error>> {
+        let page = page.max(1);
+        let page_size = page_size.clamp(1, 1000);
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM events e WHERE session_id = ?1 AND (?2 IS NULL OR event_type = ?2)",
+            params![session_id, event_type],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM events e WHERE session_id = ?1 AND (?2 IS NULL OR event_type = ?2)
+             ORDER BY id LIMIT ?3 OFFSET ?4",
+            EVENT_COLUMNS
+        ))?;
+        let events = stmt
+            .query_map(
+                params![session_id, event_type, page_size, (page - 1) * page_size],
+                event_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(EventPage {
+            total,
+            page,
+            page_size,
+            events,
+        })
+    }
+
+    /// 某个会话的全部消息，导出时使用
+    pub fn all_session_events(
+        &self,
+        session_id: i64,
+    ) -> Result<Vec<ArchivedEvent>, Box<dyn std::error::Error>> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM events e WHERE e.session_id = ?1 ORDER BY e.id",
+            EVENT_COLUMNS
+        ))?;
+        let events = stmt
+            .query_map(params![session_id], event_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+
+    /// 搜索聊天记录，结果按时间倒序
+    pub fn search_chat(
+        &self,
+        search: &ChatSearch,
+    ) -> Result<ChatSearchPage, Box<dyn std::error::Error>> {
+        let page = search.page.max(1);
+        let page_size = if search.page_size == 0 {
+            20
+        } else {
+            search.page_size.min(200)
+        };
+        let keyword = search.keyword.trim();
+
+        // trigram 分词至少需要 3 个字符，更短的关键词直接用 LIKE 匹配
+        let (source, keyword_filter, keyword_param) = if keyword.is_empty() {
+            ("events e", "1 = 1", None)
+        } else if keyword.chars().count() >= 3 {
+            (
+                "chat_fts f JOIN events e ON e.id = f.rowid",
+                "chat_fts MATCH ?1",
+                Some(format!("\"{}\"", keyword.replace('"', "\"\""))),
+            )
+        } else {
+            (
+                "events e",
+                "(e.content LIKE ?1 ESCAPE '\\' OR e.nickname LIKE ?1 ESCAPE '\\')",
+                Some(format!("%{}%", escape_like(keyword))),
+            )
+        };
+        let filter = format!(
+            "{} AND e.event_type = 'chat'
+             AND (?2 IS NULL OR e.room_id = ?2)
+             AND (?3 IS NULL OR e.received_at >= ?3)
+             AND (?4 IS NULL OR e.received_at <= ?4)
+             AND (?5 IS NULL OR e.user_id = ?5 OR e.nickname = ?5)",
+            keyword_filter
+        );
+        let since = search.since.map(|t| t as i64);
+        let until = search.until.map(|t| t as i64);
+
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE {}", source, filter),
+            params![keyword_param, search.room_id, since, until, search.user],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY e.id DESC LIMIT ?6 OFFSET ?7",
+            EVENT_COLUMNS, source, filter
+        ))?;
+        let events = stmt
+            .query_map(
+                params![
+                    keyword_param,
+                    search.room_id,
+                    since,
+                    until,
+                    search.user,
+                    page_size,
+                    (page - 1) * page_size
+                ],
+                event_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut hits = Vec::with_capacity(events.len());
+        for event in events {
+            let (before, after) = if search.context > 0 {
+                chat_context(&conn, &event, search.context)?
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            hits.push(ChatHit {
+                before,
+                event,
+                after,
+            });
+        }
+        Ok(ChatSearchPage {
+            total,
+            page,
+            page_size,
+            hits,
+        })
+    }
+}
+
+// 同一会话里某条消息前后的聊天消息
+fn chat_context(
+    conn: &Connection,
+    event: &ArchivedEvent,
+    count: u32,
+) -> rusqlite::Result<(Vec<ArchivedEvent>, Vec<ArchivedEvent>)> {
+    let mut before = conn
+        .prepare(&format!(
+            "SELECT {} FROM events e WHERE e.session_id = ?1 AND e.event_type = 'chat' AND e.id < ?2
+             ORDER BY e.id DESC LIMIT ?3",
+            EVENT_COLUMNS
+        ))?
+        .query_map(params![event.session_id, event.id, count], event_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    before.reverse();
+    let after = conn
+        .prepare(&format!(
+            "SELECT {} FROM events e WHERE e.session_id = ?1 AND e.event_type = 'chat' AND e.id > ?2
+             ORDER BY e.id LIMIT ?3",
+            EVENT_COLUMNS
+        ))?
+        .query_map(params![event.session_id, event.id, count], event_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((before, after))
+}
+
+// 聊天内容和昵称的全文索引，第一次创建时把已有的聊天记录补进去
+fn create_chat_index(conn: &Connection) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'chat_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(());
+    }
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE chat_fts USING fts5(content, nickname, tokenize = 'trigram');
+        INSERT INTO chat_fts (rowid, content, nickname)
+            SELECT id, COALESCE(content, ''), nickname FROM events WHERE event_type = 'chat';",
+    )
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+const EVENT_COLUMNS: &str = "e.id, e.session_id, e.room_id, e.msg_id, e.method, e.event_type, e.user_id, e.nickname, e.content, e.received_at, e.data";
+
+fn event_from_row(row: &rusqlite::Row) -> rusqlite::Result<ArchivedEvent> {
+    let data: String = row.get(10)?;
+    Ok(ArchivedEvent {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        room_id: row.get(2)?,
+        msg_id: row.get(3)?,
+        method: row.get(4)?,
+        event_type: row.get(5)?,
+        user_id: row.get(6)?,
+        nickname: row.get(7)?,
+        content: row.get(8)?,
+        received_at: row.get::<_, i64>(9)? as u64,
+        data: serde_json::from_str(&data).unwrap_or(serde_json::Value::Null),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EventUser;
+
+    fn archive() -> Archive {
+        Archive::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn chat(msg_id: &str, nickname: &str, content: &str) -> (proto::Message, LiveEvent) {
+        let message = proto::Message {
+            method: "WebcastChatMessage".to_string(),
+            msg_id: 1,
+            ..Default::default()
+        };
+        let event = LiveEvent::Chat {
+            msg_id: msg_id.to_string(),
+            user: EventUser {
+                id: format!("uid-{}", nickname),
+                nick_name: nickname.to_string(),
+                ..Default::default()
+            },
+            content: content.to_string(),
+        };
+        (message, event)
+    }
+
+    fn record_chat(archive: &Archive, room_id: &str, msg_id: &str, nickname: &str, content: &str) {
+        let (message, event) = chat(msg_id, nickname, content);
+        archive.record(room_id, &message, &event).unwrap();
+    }
+
+    #[test]
+    fn session_lifecycle() {
+        let archive = archive();
+        let first = archive
+            .begin_session("100", "https://live.douyin.com/1", "标题", "主播")
+            .unwrap();
+        record_chat(&archive, "100", "11", "小明", "第一场");
+        archive.end_session("100").unwrap();
+
+        // 结束后再收到消息不能记到已经结束的会话里
+        record_chat(&archive, "100", "12", "小明", "第二场");
+        let sessions = archive.list_sessions(Some("100"), 10, 0).unwrap();
+        assert_eq!(sessions.len(), 2);
+        let (latest, ended) = (&sessions[0], &sessions[1]);
+        assert_eq!(ended.id, first);
+        assert!(ended.ended_at.is_some());
+        assert_eq!(ended.event_count, 1);
+        assert_ne!(latest.id, first);
+        assert!(latest.ended_at.is_none());
+        assert_eq!(latest.event_count, 1);
+
+        // 重新打开时也只沿用还没结束的会话
+        archive.end_session("100").unwrap();
+        archive.current.lock().unwrap().clear();
+        record_chat(&archive, "100", "13", "小明", "第三场");
+        assert_eq!(archive.list_sessions(Some("100"), 10, 0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn record_and_query() {
+        let archive = archive();
+        let session = archive.begin_session("100", "", "", "").unwrap();
+        record_chat(&archive, "100", "7401", "小明", "主播晚上好");
+        let like = LiveEvent::Like {
+            msg_id: "7402".to_string(),
+            user: EventUser::default(),
+            count: 3,
+            total: 30,
+        };
+        let message = proto::Message {
+            method: "WebcastLikeMessage".to_string(),
+            ..Default::default()
+        };
+        archive.record("100", &message, &like).unwrap();
+
+        let page = archive.session_events(session, None, 1, 10).unwrap();
+        assert_eq!(page.total, 2);
+        let event = &page.events[0];
+        assert_eq!(event.msg_id, "7401");
+        assert_eq!(event.method, "WebcastChatMessage");
+        assert_eq!(event.event_type, "chat");
+        assert_eq!(event.nickname, "小明");
+        assert_eq!(event.content.as_deref(), Some("主播晚上好"));
+        assert_eq!(event.data["type"], "chat");
+
+        let likes = archive
+            .session_events(session, Some("like"), 1, 10)
+            .unwrap();
+        assert_eq!(likes.total, 1);
+        assert_eq!(likes.events[0].msg_id, "7402");
+        assert_eq!(archive.all_session_events(session).unwrap().len(), 2);
+    }
+
+    fn search(archive: &Archive, keyword: &str) -> ChatSearchPage {
+        let search = ChatSearch {
+            keyword: keyword.to_string(),
+            ..Default::default()
+        };
+        archive.search_chat(&search).unwrap()
+    }
+
+    fn contents(page: &ChatSearchPage) -> Vec<&str> {
+        page.hits
+            .iter()
+            .filter_map(|hit| hit.event.content.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn search_chat_trigram_and_like() {
+        let archive = archive();
+        record_chat(&archive, "100", "1", "小明", "主播晚上好");
+        record_chat(&archive, "100", "2", "路人甲", "今天唱什么歌");
+        record_chat(&archive, "200", "3", "小红", "晚上好呀 100%");
+
+        // 3 个字及以上走 trigram 全文索引
+        let page = search(&archive, "晚上好");
+        assert_eq!(page.total, 2);
+        assert_eq!(contents(&page), ["晚上好呀 100%", "主播晚上好"]);
+        assert_eq!(search(&archive, "路人甲").total, 1);
+
+        // 更短的关键词用 LIKE，% 要转义
+        assert_eq!(contents(&search(&archive, "唱")), ["今天唱什么歌"]);
+        assert_eq!(search(&archive, "小").total, 2);
+        assert_eq!(contents(&search(&archive, "0%")), ["晚上好呀 100%"]);
+        assert_eq!(search(&archive, "%").total, 1);
+
+        let search = ChatSearch {
+            keyword: "晚上好".to_string(),
+            room_id: Some("100".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(archive.search_chat(&search).unwrap().total, 1);
+    }
+
+    #[test]
+    fn search_chat_context() {
+        let archive = archive();
+        for (i, content) in ["一", "二", "关键词在这里", "四", "五"].iter().enumerate() {
+            record_chat(&archive, "100", &i.to_string(), "小明", content);
+        }
+        let search = ChatSearch {
+            keyword: "关键词".to_string(),
+            context: 1,
+            ..Default::default()
+        };
+        let page = archive.search_chat(&search).unwrap();
+        assert_eq!(page.total, 1);
+        let hit = &page.hits[0];
+        assert_eq!(hit.before[0].content.as_deref(), Some("二"));
+        assert_eq!(hit.after[0].content.as_deref(), Some("四"));
+    }
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+use crate::utils::paths::livebox_dir;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CookieData {
     pub name: String,
@@ -10,7 +12,7 @@ pub struct CookieData {
     pub path: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct CookieStore {
     pub cookies: Vec<CookieData>,
 }
@@ -75,12 +77,7 @@ impl CookieStore {
 
     /// 获取默认的 cookie 文件路径
     pub fn get_default_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let home = std::env::var("HOME")
-            .or_else(|_| std::env::var("USERPROFILE"))
-            .unwrap_or_else(|_| ".".to_string());
-
-        let mut path = PathBuf::from(home);
-        path.push(".livebox");
+        let mut path = livebox_dir();
         path.push("douyin_cookies.json");
 
         Ok(path)
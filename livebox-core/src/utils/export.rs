@@ -0,0 +1,409 @@
+use chrono::{Local, TimeZone};
+use rust_xlsxwriter::Workbook;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::archive::{Archive, ArchivedEvent};
+use crate::utils::paths::livebox_dir;
+
+// 导出格式
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+// 导出结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub event_count: usize,
+    // 礼物总钻石数（单价 x 数量，连击只算一次）
+    pub diamond_total: u64,
+}
+
+/// 默认导出位置 ~/.livebox/exports/session-{id}.{ext}
+pub fn default_export_path(session_id: i64, format: ExportFormat) -> PathBuf {
+    livebox_dir()
+        .join("exports")
+        .join(format!("session-{}.{}", session_id, format.extension()))
+}
+
+/// 把一次会话保存的消息导出到文件
+pub fn export_session(
+    archive: &Archive,
+    session_id: i64,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<ExportSummary, Box<dyn std::error::Error>> {
+    let events = archive.all_session_events(session_id)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let diamonds = gift_diamonds(&events);
+    match format {
+        ExportFormat::Csv => write_csv(&events, &diamonds, path)?,
+        ExportFormat::Jsonl => write_jsonl(&events, path)?,
+        ExportFormat::Xlsx => write_xlsx(&events, &diamonds, path)?,
+    }
+    let diamond_total = diamonds.values().sum();
+    eprintln!(
+        "📤 [导出] 会话 {} 共 {} 条消息，已导出到 {:?}",
+        session_id,
+        events.len(),
+        path
+    );
+    Ok(ExportSummary {
+        path: path.to_string_lossy().to_string(),
+        event_count: events.len(),
+        diamond_total,
+    })
+}
+
+// 所有消息放在一个 CSV 里，礼物相关的列只有礼物消息才有值
+fn write_csv(
+    events: &[ArchivedEvent],
+    diamonds: &HashMap<i64, u64>,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = BufWriter::new(File::create(path)?);
+    // 带上 UTF-8 BOM，Excel 直接打开时中文不会乱码
+    file.write_all("\u{feff}".as_bytes())?;
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record([
+        "time",
+        "type",
+        "method",
+        "msg_id",
+        "user_id",
+        "nickname",
+        "content",
+        "gift_name",
+        "diamond_count",
+        "repeat_count",
+        "diamond_total",
+    ])?;
+    for event in events {
+        let is_gift = event.event_type == "gift";
+        let gift_column = |value: String| if is_gift { value } else { String::new() };
+        writer.write_record([
+            format_time(event.received_at),
+            event.event_type.clone(),
+            event.method.clone(),
+            event.msg_id.clone(),
+            event.user_id.clone(),
+            event.nickname.clone(),
+            event.content.clone().unwrap_or_default(),
+            gift_column(field_str(&event.data, "gift_name")),
+            gift_column(field_u64(&event.data, "diamond_count").to_string()),
+            gift_column(field_u64(&event.data, "repeat_count").to_string()),
+            gift_column(diamond_of(diamonds, event).to_string()),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 每行一条 ArchivedEvent 的 JSON
+fn write_jsonl(events: &[ArchivedEvent], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 每种消息一个工作表，最后加一个汇总表
+fn write_xlsx(
+    events: &[ArchivedEvent],
+    diamonds: &HashMap<i64, u64>,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut workbook = Workbook::new();
+
+    let by_type = |event_type: &str| -> Vec<&ArchivedEvent> {
+        events.iter().filter(|e| e.event_type == event_type).collect()
+    };
+
+    let chats = by_type("chat");
+    let sheet = workbook.add_worksheet().set_name("聊天")?;
+    write_row(sheet, 0, &["时间", "用户ID", "昵称", "内容"].map(Cell::from))?;
+    for (i, e) in chats.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32 + 1,
+            &[
+                Cell::Text(format_time(e.received_at)),
+                Cell::Text(e.user_id.clone()),
+                Cell::Text(e.nickname.clone()),
+                Cell::Text(e.content.clone().unwrap_or_default()),
+            ],
+        )?;
+    }
+
+    let gifts = by_type("gift");
+    let sheet = workbook.add_worksheet().set_name("礼物")?;
+    write_row(
+        sheet,
+        0,
+        &["时间", "用户ID", "昵称", "礼物ID", "礼物", "单价(钻石)", "数量", "连击", "总钻石"]
+            .map(Cell::from),
+    )?;
+    for (i, e) in gifts.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32 + 1,
+            &[
+                Cell::Text(format_time(e.received_at)),
+                Cell::Text(e.user_id.clone()),
+                Cell::Text(e.nickname.clone()),
+                Cell::Text(field_u64(&e.data, "gift_id").to_string()),
+                Cell::Text(field_str(&e.data, "gift_name")),
+                Cell::Number(field_u64(&e.data, "diamond_count") as f64),
+                Cell::Number(field_u64(&e.data, "repeat_count") as f64),
+                Cell::Number(field_u64(&e.data, "combo_count") as f64),
+                Cell::Number(diamond_of(diamonds, e) as f64),
+            ],
+        )?;
+    }
+
+    let likes = by_type("like");
+    let sheet = workbook.add_worksheet().set_name("点赞")?;
+    write_row(sheet, 0, &["时间", "用户ID", "昵称", "点赞数", "直播间总点赞"].map(Cell::from))?;
+    for (i, e) in likes.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32 + 1,
+            &[
+                Cell::Text(format_time(e.received_at)),
+                Cell::Text(e.user_id.clone()),
+                Cell::Text(e.nickname.clone()),
+                Cell::Number(field_u64(&e.data, "count") as f64),
+                Cell::Number(field_u64(&e.data, "total") as f64),
+            ],
+        )?;
+    }
+
+    let members = by_type("member");
+    let sheet = workbook.add_worksheet().set_name("进场")?;
+    write_row(sheet, 0, &["时间", "用户ID", "昵称", "在线人数"].map(Cell::from))?;
+    for (i, e) in members.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32 + 1,
+            &[
+                Cell::Text(format_time(e.received_at)),
+                Cell::Text(e.user_id.clone()),
+                Cell::Text(e.nickname.clone()),
+                Cell::Number(field_u64(&e.data, "member_count") as f64),
+            ],
+        )?;
+    }
+
+    let follows = by_type("follow");
+    let sheet = workbook.add_worksheet().set_name("关注")?;
+    write_row(sheet, 0, &["时间", "用户ID", "昵称", "粉丝数"].map(Cell::from))?;
+    for (i, e) in follows.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32 + 1,
+            &[
+                Cell::Text(format_time(e.received_at)),
+                Cell::Text(e.user_id.clone()),
+                Cell::Text(e.nickname.clone()),
+                Cell::Number(field_u64(&e.data, "follow_count") as f64),
+            ],
+        )?;
+    }
+
+    // 汇总：各类消息数量和礼物总钻石数
+    let diamond_total: u64 = diamonds.values().sum();
+    let sheet = workbook.add_worksheet().set_name("汇总")?;
+    let summary = [
+        ("聊天条数", chats.len() as f64),
+        ("礼物条数", gifts.len() as f64),
+        ("点赞条数", likes.len() as f64),
+        ("进场人次", members.len() as f64),
+        ("关注人数", follows.len() as f64),
+        ("礼物总钻石", diamond_total as f64),
+        // 和界面上的预估收益算法一致：10 钻石 = 1 元，主播到手 50%
+        ("预估收益(元)", diamond_total as f64 / 10.0 * 0.5),
+    ];
+    for (i, (name, value)) in summary.iter().enumerate() {
+        write_row(
+            sheet,
+            i as u32,
+            &[Cell::Text(name.to_string()), Cell::Number(*value)],
+        )?;
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+fn write_row(
+    sheet: &mut rust_xlsxwriter::Worksheet,
+    row: u32,
+    cells: &[Cell],
+) -> Result<(), rust_xlsxwriter::XlsxError> {
+    for (col, cell) in cells.iter().enumerate() {
+        match cell {
+            Cell::Text(text) => sheet.write_string(row, col as u16, text)?,
+            Cell::Number(number) => sheet.write_number(row, col as u16, *number)?,
+        };
+    }
+    Ok(())
+}
+
+// 每条礼物消息新增的钻石数 = 单价 x 新增数量，key 是 ArchivedEvent.id，和前端计算主播收益的方式一致
+//
+// 连击礼物每次连击都会再发一条消息，repeat_count 是到这条为止的累计数量，结束时还会带着
+// repeat_end 重发一次最终数量，所以按同一用户同一礼物上一条消息的差值算；
+// 数量比上一条小说明是新的一次连击。没有 combo 字段的旧记录也按连击处理
+fn gift_diamonds(events: &[ArchivedEvent]) -> HashMap<i64, u64> {
+    let mut last_repeat: HashMap<(&str, u64), u64> = HashMap::new();
+    let mut diamonds = HashMap::new();
+    for event in events.iter().filter(|e| e.event_type == "gift") {
+        let repeat = field_u64(&event.data, "repeat_count");
+        let combo = field_bool(&event.data, "combo").unwrap_or(true);
+        let added = if combo {
+            let key = (event.user_id.as_str(), field_u64(&event.data, "gift_id"));
+            let previous = last_repeat.insert(key, repeat).unwrap_or(0);
+            if field_bool(&event.data, "repeat_end") == Some(true) {
+                last_repeat.remove(&key);
+            }
+            if repeat >= previous {
+                repeat - previous
+            } else {
+                repeat
+            }
+        } else {
+            repeat
+        };
+        diamonds.insert(event.id, field_u64(&event.data, "diamond_count") * added);
+    }
+    diamonds
+}
+
+fn diamond_of(diamonds: &HashMap<i64, u64>, event: &ArchivedEvent) -> u64 {
+    diamonds.get(&event.id).copied().unwrap_or(0)
+}
+
+fn field_u64(data: &Value, key: &str) -> u64 {
+    data.get(key).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+fn field_bool(data: &Value, key: &str) -> Option<bool> {
+    data.get(key).and_then(|v| v.as_bool())
+}
+
+fn field_str(data: &Value, key: &str) -> String {
+    data.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn format_time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn gift(id: i64, user_id: &str, repeat_count: u64, repeat_end: bool) -> ArchivedEvent {
+        ArchivedEvent {
+            id,
+            session_id: 1,
+            room_id: "100".to_string(),
+            msg_id: id.to_string(),
+            method: "WebcastGiftMessage".to_string(),
+            event_type: "gift".to_string(),
+            user_id: user_id.to_string(),
+            nickname: String::new(),
+            content: None,
+            received_at: 0,
+            data: json!({
+                "type": "gift",
+                "gift_id": 463,
+                "diamond_count": 10,
+                "repeat_count": repeat_count,
+                "repeat_end": repeat_end,
+                "combo": true,
+            }),
+        }
+    }
+
+    #[test]
+    fn combo_counts_once() {
+        let mut events = vec![
+            // 一次 3 连击，结束时重发最终数量
+            gift(1, "a", 1, false),
+            gift(2, "a", 2, false),
+            gift(3, "b", 1, false),
+            gift(4, "a", 3, false),
+            gift(5, "a", 3, true),
+            gift(6, "b", 1, true),
+            // 同一个人又送了一次，从 1 开始
+            gift(7, "a", 1, false),
+            gift(8, "a", 2, true),
+        ];
+        let diamonds = gift_diamonds(&events);
+        assert_eq!(diamonds.values().sum::<u64>(), (3 + 1 + 2) * 10);
+        assert_eq!(diamonds[&4], 10);
+        assert_eq!(diamonds[&5], 0);
+        assert_eq!(diamonds[&7], 10);
+
+        // 不能连击的礼物每条消息都是一次
+        for event in &mut events {
+            event.data["combo"] = json!(false);
+        }
+        let diamonds = gift_diamonds(&events);
+        assert_eq!(
+            diamonds.values().sum::<u64>(),
+            (1 + 2 + 1 + 3 + 3 + 1 + 1 + 2) * 10
+        );
+    }
+
+    #[test]
+    fn missed_combo_start() {
+        // 连接中途才收到的连击，第一条就是累计值
+        let events = [gift(1, "a", 5, false), gift(2, "a", 6, true)];
+        assert_eq!(gift_diamonds(&events).values().sum::<u64>(), 60);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+use crate::proto::{self, douyin};
+use crate::utils::http::shared_client;
+use crate::utils::push_queue::PushQueue;
+use crate::utils::time::now_millis;
+use crate::utils::webhook_sign;
+
+// 推送配置，对应设置弹窗里的推送地址和消息类型（chat/gift/like/follow/comein）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ForwardConfig {
+    pub url: String,
+    pub types: Vec<String>,
+    // 共享密钥，设置后每个推送请求都会带上时间戳和 HMAC-SHA256 签名
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl ForwardConfig {
+    pub fn is_enabled(&self, msg_type: &str) -> bool {
+        !self.url.is_empty() && self.types.iter().any(|t| t == msg_type)
+    }
+}
+
+// 推送的消息体，格式和 example_receiver.py 约定的一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushPayload {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub data: PushData,
+    pub raw: serde_json::Value,
+    pub timestamp: u64,
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushData {
+    pub id: String,
+    pub name: String,
+    pub msg: String,
+}
+
+impl PushPayload {
+    /// 把弹幕消息转换为推送格式，不支持推送的消息类型返回 None
+    pub fn from_message(
+        room_id: &str,
+        message: &proto::Message,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error + Send + Sync>> {
+        let (msg_type, data, raw) = match message.method.as_str() {
+            "WebcastChatMessage" => {
+                let msg: douyin::ChatMessage = proto::decode_message(message)?;
+                let data = push_data(&msg.common, &msg.user, msg.content.clone());
+                ("chat", data, serde_json::to_value(&msg)?)
+            }
+            "WebcastGiftMessage" => {
+                let msg: douyin::GiftMessage = proto::decode_message(message)?;
+                let gift_name = msg.gift.as_ref().map(|g| g.name.as_str()).unwrap_or("");
+                let text = format!("送出{} x{}个", gift_name, msg.repeat_count);
+                let data = push_data(&msg.common, &msg.user, text);
+                ("gift", data, serde_json::to_value(&msg)?)
+            }
+            "WebcastLikeMessage" => {
+                let msg: douyin::LikeMessage = proto::decode_message(message)?;
+                let data = push_data(&msg.common, &msg.user, "为主播点赞了".to_string());
+                ("like", data, serde_json::to_value(&msg)?)
+            }
+            "WebcastSocialMessage" => {
+                let msg: douyin::SocialMessage = proto::decode_message(message)?;
+                let data = push_data(&msg.common, &msg.user, "关注了主播".to_string());
+                ("follow", data, serde_json::to_value(&msg)?)
+            }
+            "WebcastMemberMessage" => {
+                let msg: douyin::MemberMessage = proto::decode_message(message)?;
+                let data = push_data(&msg.common, &msg.user, "来了".to_string());
+                ("comein", data, serde_json::to_value(&msg)?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(PushPayload {
+            msg_type: msg_type.to_string(),
+            data,
+            raw,
+            timestamp: now_millis(),
+            room_id: room_id.to_string(),
+        }))
+    }
+}
+
+// 每次推送的结果，交给界面展示
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardStatus {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub id: String,
+    pub url: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    // 包含本次在内一共推送了几次
+    pub attempts: u32,
+    // 是否因为失败次数过多被放进了死信文件
+    pub dead_letter: bool,
+}
+
+// 把弹幕消息 POST 到配置的推送地址，消息先写入持久化队列，由 PushQueue::run 负责推送和重试
+#[derive(Clone)]
+pub struct Forwarder {
+    config: Arc<RwLock<ForwardConfig>>,
+    queue: Arc<PushQueue>,
+}
+
+impl Forwarder {
+    pub fn new(queue: Arc<PushQueue>) -> Self {
+        Forwarder {
+            config: Arc::new(RwLock::new(ForwardConfig::default())),
+            queue,
+        }
+    }
+
+    pub fn queue(&self) -> &Arc<PushQueue> {
+        &self.queue
+    }
+
+    pub fn config(&self) -> ForwardConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    pub fn set_config(&self, config: ForwardConfig) {
+        eprintln!("📮 [推送] 推送地址: {}, 消息类型: {:?}", config.url, config.types);
+        self.queue.set_secret(&config.url, config.secret.as_deref());
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// 如果消息类型已勾选，就把消息放进推送队列
+    pub fn forward(&self, room_id: &str, message: &proto::Message) {
+        let config = self.config();
+        if config.url.is_empty() {
+            return;
+        }
+        let payload = match PushPayload::from_message(room_id, message) {
+            Ok(Some(payload)) if config.is_enabled(&payload.msg_type) => payload,
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("⚠️ [推送] 解析 {} 失败: {}", message.method, e);
+                return;
+            }
+        };
+        let signed = config.secret.as_ref().is_some_and(|s| !s.is_empty());
+        self.queue.push(&config.url, signed, payload);
+    }
+}
+
+/// 发送一条推送，返回推送结果；有密钥时附带时间戳和签名请求头
+pub async fn deliver(url: &str, secret: Option<&str>, payload: &PushPayload) -> ForwardStatus {
+    let mut status = ForwardStatus {
+        msg_type: payload.msg_type.clone(),
+        id: payload.data.id.clone(),
+        url: url.to_string(),
+        ok: false,
+        status: None,
+        error: None,
+        attempts: 0,
+        dead_letter: false,
+    };
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            status.error = Some(e.to_string());
+            return status;
+        }
+    };
+    let request = shared_client()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    let request = webhook_sign::sign_request(request, secret, &body);
+    match request.body(body).send().await {
+        Ok(response) => {
+            status.status = Some(response.status().as_u16());
+            status.ok = response.status().is_success();
+            if !status.ok {
+                status.error = response.status().canonical_reason().map(String::from);
+            }
+        }
+        Err(e) => status.error = Some(e.to_string()),
+    }
+    status
+}
+
+fn push_data(common: &Option<douyin::Common>, user: &Option<douyin::User>, msg: String) -> PushData {
+    PushData {
+        id: common
+            .as_ref()
+            .map(|c| c.msg_id.to_string())
+            .unwrap_or_default(),
+        name: user.as_ref().map(|u| u.nick_name.clone()).unwrap_or_default(),
+        msg,
+    }
+}
@@ -0,0 +1,15 @@
+use reqwest::Client;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// 全局共用的 HTTP 客户端，复用连接池；需要单独 Cookie 的抓取请求仍然各自创建 Client
+pub fn shared_client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("创建 HTTP 客户端失败")
+    })
+}
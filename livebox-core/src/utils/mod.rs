@@ -0,0 +1,18 @@
+pub mod cookie_store;
+pub mod http;
+pub mod paths;
+pub mod time;
+
+// 消息存档和导出（SQLite、CSV、Excel）
+#[cfg(feature = "storage")]
+pub mod archive;
+#[cfg(feature = "storage")]
+pub mod export;
+
+// 推送到外部地址（持久化队列、HMAC 签名）
+#[cfg(feature = "forwarding")]
+pub mod forwarder;
+#[cfg(feature = "forwarding")]
+pub mod push_queue;
+#[cfg(feature = "forwarding")]
+pub mod webhook_sign;
@@ -0,0 +1,12 @@
+use std::path::PathBuf;
+
+/// LiveBox 的数据目录 ~/.livebox，Cookie、推送队列等文件都放在这里
+pub fn livebox_dir() -> PathBuf {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+
+    let mut path = PathBuf::from(home);
+    path.push(".livebox");
+    path
+}
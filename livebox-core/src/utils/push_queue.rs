@@ -0,0 +1,572 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+
+use crate::utils::forwarder::{deliver, ForwardStatus, PushPayload};
+use crate::utils::paths::livebox_dir;
+use crate::utils::time::now_millis;
+
+// 同一毫秒内入队的消息用序号区分
+static SEQ: AtomicU64 = AtomicU64::new(0);
+
+// 队列变化后最多隔这么久写一次文件；接收端出故障时消息堆积很快，不能每条消息都重写整个文件
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+// 需要签名的消息在密钥还没设置时（比如刚启动、界面还没同步配置）先等这么久再看
+const SECRET_WAIT: Duration = Duration::from_secs(30);
+
+// 重试策略：第 n 次失败后等待 base_delay * 2^(n-1)，最多等待 max_delay，失败 max_attempts 次后进入死信文件
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 8,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 attempts 次失败后需要等待的时间
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+// 队列里的一条待推送消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedDelivery {
+    pub id: String,
+    pub url: String,
+    // 入队时是否设置了推送密钥；密钥本身不写进文件，推送时按地址从当前配置里取
+    #[serde(default)]
+    pub signed: bool,
+    pub payload: PushPayload,
+    pub attempts: u32,
+    pub created_at: u64,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+// 队列当前的状态，给界面查看
+#[derive(Debug, Clone, Serialize)]
+pub struct PushQueueSnapshot {
+    pub pending: Vec<QueuedDelivery>,
+    pub dead_letters: Vec<QueuedDelivery>,
+}
+
+// 持久化的推送队列：待推送的消息保存在 ~/.livebox/push_queue.json，
+// 多次失败的消息追加到 ~/.livebox/push_dead_letter.jsonl，程序重启后继续推送
+pub struct PushQueue {
+    queue_path: PathBuf,
+    dead_letter_path: PathBuf,
+    policy: RetryPolicy,
+    items: Mutex<Vec<QueuedDelivery>>,
+    notify: Notify,
+    // 队列有还没写进文件的变化
+    dirty: AtomicBool,
+    // 保证同一时间只有一个写文件的操作
+    write_lock: Mutex<()>,
+    // 推送地址对应的密钥，只保存在内存里
+    secrets: RwLock<HashMap<String, String>>,
+}
+
+impl PushQueue {
+    /// 打开默认位置的队列文件
+    pub fn open_default() -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = livebox_dir();
+        Self::open(
+            dir.join("push_queue.json"),
+            dir.join("push_dead_letter.jsonl"),
+            RetryPolicy::default(),
+        )
+    }
+
+    pub fn open(
+        queue_path: PathBuf,
+        dead_letter_path: PathBuf,
+        policy: RetryPolicy,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let items: Vec<QueuedDelivery> = if queue_path.exists() {
+            let content = fs::read_to_string(&queue_path)?;
+            serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("⚠️ [推送队列] 队列文件解析失败，已忽略: {}", e);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        if !items.is_empty() {
+            eprintln!("📮 [推送队列] 恢复了 {} 条未完成的推送", items.len());
+        }
+        Ok(PushQueue {
+            queue_path,
+            dead_letter_path,
+            policy,
+            items: Mutex::new(items),
+            notify: Notify::new(),
+            dirty: AtomicBool::new(false),
+            write_lock: Mutex::new(()),
+            secrets: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// 设置推送地址的签名密钥，为空时清除；等着这个密钥的消息马上开始推送
+    pub fn set_secret(&self, url: &str, secret: Option<&str>) {
+        let secret = secret.filter(|s| !s.is_empty());
+        if let Ok(mut secrets) = self.secrets.write() {
+            match secret {
+                Some(secret) => secrets.insert(url.to_string(), secret.to_string()),
+                None => secrets.remove(url),
+            };
+        }
+        if secret.is_none() {
+            return;
+        }
+        let now = now_millis();
+        if let Ok(mut items) = self.items.lock() {
+            for delivery in items.iter_mut().filter(|d| d.signed && d.url == url) {
+                delivery.next_attempt_at = delivery.next_attempt_at.min(now);
+            }
+        }
+        self.notify.notify_one();
+    }
+
+    fn secret_for(&self, url: &str) -> Option<String> {
+        self.secrets.read().ok()?.get(url).cloned()
+    }
+
+    /// 加入一条待推送的消息，signed 表示需要用这个地址的密钥签名
+    pub fn push(&self, url: &str, signed: bool, payload: PushPayload) {
+        let now = now_millis();
+        let delivery = QueuedDelivery {
+            id: format!("{}-{}", now, SEQ.fetch_add(1, Ordering::Relaxed)),
+            url: url.to_string(),
+            signed,
+            payload,
+            attempts: 0,
+            created_at: now,
+            next_attempt_at: now,
+            last_error: None,
+        };
+        if let Ok(mut items) = self.items.lock() {
+            items.push(delivery);
+        }
+        // 由后台任务统一写文件，这里只标记一下
+        self.dirty.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    /// 查看待推送和已经进入死信的消息
+    pub fn snapshot(&self) -> Result<PushQueueSnapshot, Box<dyn std::error::Error>> {
+        let pending = self.items.lock().map(|items| items.clone()).unwrap_or_default();
+        Ok(PushQueueSnapshot {
+            pending,
+            dead_letters: read_dead_letters(&self.dead_letter_path)?,
+        })
+    }
+
+    /// 把死信重新放回队列，ids 为空时全部重放，返回重放的数量
+    pub fn replay(&self, ids: Option<Vec<String>>) -> Result<usize, Box<dyn std::error::Error>> {
+        let dead_letters = read_dead_letters(&self.dead_letter_path)?;
+        let (replay, keep): (Vec<_>, Vec<_>) = dead_letters
+            .into_iter()
+            .partition(|d| ids.as_ref().is_none_or(|ids| ids.contains(&d.id)));
+        if replay.is_empty() {
+            return Ok(0);
+        }
+
+        let now = now_millis();
+        let count = replay.len();
+        if let Ok(mut items) = self.items.lock() {
+            items.extend(replay.into_iter().map(|mut d| {
+                d.attempts = 0;
+                d.next_attempt_at = now;
+                d.last_error = None;
+                d
+            }));
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        self.flush();
+        write_dead_letters(&self.dead_letter_path, &keep)?;
+        eprintln!("📮 [推送队列] 重放了 {} 条失败的推送", count);
+        self.notify.notify_one();
+        Ok(count)
+    }
+
+    /// 后台推送任务，按顺序推送到期的消息，每次推送的结果通过 status_tx 发出
+    pub async fn run(self: Arc<Self>, status_tx: mpsc::UnboundedSender<ForwardStatus>) {
+        let mut last_flush = Instant::now();
+        loop {
+            let now = now_millis();
+            let due: Vec<QueuedDelivery> = self
+                .items
+                .lock()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter(|d| d.next_attempt_at <= now)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            for delivery in due {
+                let secret = self.secret_for(&delivery.url);
+                if delivery.signed && secret.is_none() {
+                    // 不能不签名就发出去，等配置同步过来
+                    self.postpone(&delivery.id, SECRET_WAIT);
+                    continue;
+                }
+                let status = deliver(&delivery.url, secret.as_deref(), &delivery.payload).await;
+                let status = self.complete(&delivery.id, status);
+                let _ = status_tx.send(status);
+                // 一批消息推送得很慢时，中途也按间隔保存
+                if last_flush.elapsed() >= PERSIST_INTERVAL {
+                    self.flush();
+                    last_flush = Instant::now();
+                }
+            }
+            if self.dirty.load(Ordering::Relaxed) && last_flush.elapsed() >= PERSIST_INTERVAL {
+                self.flush();
+                last_flush = Instant::now();
+            }
+
+            // 还有没保存的变化时，最晚到下一个保存时间醒来
+            let flush_wait = self
+                .dirty
+                .load(Ordering::Relaxed)
+                .then(|| PERSIST_INTERVAL.saturating_sub(last_flush.elapsed()));
+            let wait = match (self.next_wait(), flush_wait) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            match wait {
+                Some(wait) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = self.notify.notified() => {}
+                    }
+                }
+                None => self.notify.notified().await,
+            }
+        }
+    }
+
+    // 记录一次推送结果：成功就移出队列，失败就安排重试或者放进死信
+    fn complete(&self, id: &str, mut status: ForwardStatus) -> ForwardStatus {
+        let Ok(mut items) = self.items.lock() else {
+            return status;
+        };
+        let Some(index) = items.iter().position(|d| d.id == id) else {
+            return status;
+        };
+
+        if status.ok {
+            let delivery = items.remove(index);
+            status.attempts = delivery.attempts + 1;
+        } else {
+            let delivery = &mut items[index];
+            delivery.attempts += 1;
+            delivery.last_error = status.error.clone();
+            status.attempts = delivery.attempts;
+            if delivery.attempts >= self.policy.max_attempts {
+                let delivery = items.remove(index);
+                eprintln!(
+                    "💀 [推送队列] 推送 {} 失败 {} 次，已放入死信文件",
+                    delivery.id, delivery.attempts
+                );
+                if let Err(e) = append_dead_letter(&self.dead_letter_path, &delivery) {
+                    eprintln!("❌ [推送队列] 写入死信文件失败: {}", e);
+                }
+                status.dead_letter = true;
+            } else {
+                let wait = self.policy.backoff(delivery.attempts);
+                delivery.next_attempt_at = now_millis() + wait.as_millis() as u64;
+                eprintln!(
+                    "🔁 [推送队列] 推送 {} 第 {} 次失败，{} 秒后重试",
+                    delivery.id,
+                    delivery.attempts,
+                    wait.as_secs()
+                );
+            }
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        status
+    }
+
+    // 推迟一条消息，不算作失败
+    fn postpone(&self, id: &str, wait: Duration) {
+        if let Ok(mut items) = self.items.lock() {
+            if let Some(delivery) = items.iter_mut().find(|d| d.id == id) {
+                delivery.next_attempt_at = now_millis() + wait.as_millis() as u64;
+            }
+        }
+    }
+
+    // 距离下一条消息到期还要等多久，队列为空时返回 None
+    fn next_wait(&self) -> Option<Duration> {
+        let items = self.items.lock().ok()?;
+        let next = items.iter().map(|d| d.next_attempt_at).min()?;
+        Some(Duration::from_millis(next.saturating_sub(now_millis())))
+    }
+
+    /// 把队列写到文件，没有变化时跳过；程序退出前调用，避免丢掉最后一秒入队的消息
+    pub fn flush(&self) {
+        let Ok(_guard) = self.write_lock.lock() else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        // 复制一份再写，写文件时不占着队列的锁，入队不会被阻塞
+        let items = self
+            .items
+            .lock()
+            .map(|items| items.clone())
+            .unwrap_or_default();
+        self.persist(&items);
+    }
+
+    // 先写临时文件再替换，避免写到一半程序退出导致队列文件损坏
+    fn persist(&self, items: &[QueuedDelivery]) {
+        let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+            if let Some(parent) = self.queue_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp = self.queue_path.with_extension("json.tmp");
+            fs::write(&tmp, serde_json::to_string(items)?)?;
+            fs::rename(&tmp, &self.queue_path)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            eprintln!("❌ [推送队列] 保存队列文件失败: {}", e);
+            // 下次再试
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn read_dead_letters(path: &Path) -> Result<Vec<QueuedDelivery>, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn write_dead_letters(
+    path: &Path,
+    items: &[QueuedDelivery],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut content = String::new();
+    for item in items {
+        content.push_str(&serde_json::to_string(item)?);
+        content.push('\n');
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn append_dead_letter(
+    path: &Path,
+    item: &QueuedDelivery,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(item)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::forwarder::PushData;
+
+    const SECRET: &str = "livebox-secret";
+
+    fn open(dir: &Path, max_attempts: u32) -> PushQueue {
+        let policy = RetryPolicy {
+            max_attempts,
+            ..RetryPolicy::default()
+        };
+        PushQueue::open(
+            dir.join("push_queue.json"),
+            dir.join("push_dead_letter.jsonl"),
+            policy,
+        )
+        .unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("livebox-{}-{}", name, now_millis()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn payload(msg: &str) -> PushPayload {
+        PushPayload {
+            msg_type: "chat".to_string(),
+            data: PushData {
+                id: "7401".to_string(),
+                name: "小明".to_string(),
+                msg: msg.to_string(),
+            },
+            raw: serde_json::Value::Null,
+            timestamp: 0,
+            room_id: "100".to_string(),
+        }
+    }
+
+    fn failed() -> ForwardStatus {
+        ForwardStatus {
+            msg_type: "chat".to_string(),
+            id: "7401".to_string(),
+            url: String::new(),
+            ok: false,
+            status: Some(500),
+            error: Some("Internal Server Error".to_string()),
+            attempts: 0,
+            dead_letter: false,
+        }
+    }
+
+    fn pending_ids(queue: &PushQueue) -> Vec<String> {
+        let snapshot = queue.snapshot().unwrap();
+        snapshot.pending.into_iter().map(|d| d.id).collect()
+    }
+
+    // 连续失败直到进入死信文件
+    fn kill(queue: &PushQueue, id: &str) {
+        for _ in 0..queue.policy.max_attempts {
+            queue.complete(id, failed());
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(5), Duration::from_secs(32));
+        assert_eq!(policy.backoff(10), policy.max_delay);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_delay);
+    }
+
+    #[test]
+    fn dead_letter_after_max_attempts() {
+        let dir = temp_dir("push-dead");
+        let queue = open(&dir, 3);
+        queue.push("http://localhost/webhook", false, payload("一"));
+        let id = pending_ids(&queue).remove(0);
+
+        let status = queue.complete(&id, failed());
+        assert_eq!((status.attempts, status.dead_letter), (1, false));
+        let status = queue.complete(&id, failed());
+        assert_eq!((status.attempts, status.dead_letter), (2, false));
+        let snapshot = queue.snapshot().unwrap();
+        assert_eq!(snapshot.pending[0].attempts, 2);
+        assert!(snapshot.pending[0].next_attempt_at > now_millis());
+
+        let status = queue.complete(&id, failed());
+        assert_eq!((status.attempts, status.dead_letter), (3, true));
+        let snapshot = queue.snapshot().unwrap();
+        assert!(snapshot.pending.is_empty());
+        assert_eq!(snapshot.dead_letters[0].id, id);
+        assert_eq!(
+            snapshot.dead_letters[0].last_error.as_deref(),
+            Some("Internal Server Error")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replay_dead_letters() {
+        let dir = temp_dir("push-replay");
+        let queue = open(&dir, 1);
+        for msg in ["一", "二", "三"] {
+            queue.push("http://localhost/webhook", false, payload(msg));
+        }
+        let ids = pending_ids(&queue);
+        for id in &ids {
+            kill(&queue, id);
+        }
+        assert_eq!(queue.snapshot().unwrap().dead_letters.len(), 3);
+
+        assert_eq!(queue.replay(Some(vec![ids[1].clone()])).unwrap(), 1);
+        let snapshot = queue.snapshot().unwrap();
+        assert_eq!(pending_ids(&queue), [ids[1].clone()]);
+        assert_eq!(snapshot.pending[0].attempts, 0);
+        assert!(snapshot.pending[0].last_error.is_none());
+        let dead: Vec<_> = snapshot.dead_letters.iter().map(|d| &d.id).collect();
+        assert_eq!(dead, [&ids[0], &ids[2]]);
+
+        assert_eq!(queue.replay(Some(vec!["missing".to_string()])).unwrap(), 0);
+        assert_eq!(queue.replay(None).unwrap(), 2);
+        let snapshot = queue.snapshot().unwrap();
+        assert_eq!(snapshot.pending.len(), 3);
+        assert!(snapshot.dead_letters.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn flush_and_reopen() {
+        let dir = temp_dir("push-reopen");
+        let url = "http://localhost/webhook";
+        let queue = open(&dir, 1);
+        queue.set_secret(url, Some(SECRET));
+        queue.push(url, true, payload("签名的消息"));
+        queue.push(url, false, payload("不签名的消息"));
+        queue.push(url, true, payload("失败的消息"));
+        let ids = pending_ids(&queue);
+        kill(&queue, &ids[2]);
+        queue.flush();
+
+        // 密钥只在内存里，不会写进队列和死信文件
+        for file in ["push_queue.json", "push_dead_letter.jsonl"] {
+            let content = fs::read_to_string(dir.join(file)).unwrap();
+            assert!(!content.contains(SECRET), "{} 里有密钥", file);
+        }
+
+        let reopened = open(&dir, 1);
+        let snapshot = reopened.snapshot().unwrap();
+        let pending: Vec<_> = snapshot
+            .pending
+            .iter()
+            .map(|d| (d.id.as_str(), d.signed, d.payload.data.msg.as_str()))
+            .collect();
+        assert_eq!(
+            pending,
+            [
+                (ids[0].as_str(), true, "签名的消息"),
+                (ids[1].as_str(), false, "不签名的消息")
+            ]
+        );
+        assert_eq!(snapshot.dead_letters.len(), 1);
+        assert_eq!(snapshot.dead_letters[0].id, ids[2]);
+        assert!(snapshot.dead_letters[0].signed);
+        // 重新打开后还不知道密钥，要等配置同步过来
+        assert_eq!(reopened.secret_for(url), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
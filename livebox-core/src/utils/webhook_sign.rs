@@ -0,0 +1,165 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::utils::time::now_millis;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 推送请求里携带的时间戳（秒）和签名的请求头
+pub const TIMESTAMP_HEADER: &str = "X-LiveBox-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-LiveBox-Signature";
+
+// 接收端默认允许的时间误差，超过这个时间的请求视为重放
+pub const DEFAULT_TOLERANCE_SECS: u64 = 300;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    // 时间戳不是合法的数字
+    InvalidTimestamp,
+    // 时间戳和当前时间相差太多
+    Expired,
+    // 签名格式不对或者和内容不匹配
+    InvalidSignature,
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::InvalidTimestamp => write!(f, "时间戳格式错误"),
+            VerifyError::Expired => write!(f, "请求已过期"),
+            VerifyError::InvalidSignature => write!(f, "签名校验失败"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// 计算签名：对 "{timestamp}.{body}" 做 HMAC-SHA256，返回 "sha256=<hex>"
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac = new_mac(secret, timestamp);
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 有密钥时给请求加上时间戳和签名请求头，body 必须是之后发送的内容
+pub fn sign_request(
+    request: reqwest::RequestBuilder,
+    secret: Option<&str>,
+    body: &[u8],
+) -> reqwest::RequestBuilder {
+    let Some(secret) = secret.filter(|s| !s.is_empty()) else {
+        return request;
+    };
+    let timestamp = now_millis() / 1000;
+    request
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, sign(secret, timestamp, body))
+}
+
+/// 接收端校验推送请求，timestamp 和 signature 分别是两个请求头的值，now 为当前时间（秒）
+pub fn verify(
+    secret: &str,
+    timestamp: &str,
+    signature: &str,
+    body: &[u8],
+    now: u64,
+    tolerance_secs: u64,
+) -> Result<(), VerifyError> {
+    let timestamp: u64 = timestamp
+        .trim()
+        .parse()
+        .map_err(|_| VerifyError::InvalidTimestamp)?;
+    if now.abs_diff(timestamp) > tolerance_secs {
+        return Err(VerifyError::Expired);
+    }
+    let expected = signature
+        .trim()
+        .strip_prefix("sha256=")
+        .and_then(|sig| hex::decode(sig).ok())
+        .ok_or(VerifyError::InvalidSignature)?;
+    let mut mac = new_mac(secret, timestamp);
+    mac.update(body);
+    // verify_slice 使用常量时间比较
+    mac.verify_slice(&expected)
+        .map_err(|_| VerifyError::InvalidSignature)
+}
+
+fn new_mac(secret: &str, timestamp: u64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC 可以使用任意长度的密钥");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "livebox-secret";
+    const BODY: &[u8] = br#"{"type":"chat"}"#;
+    const TS: u64 = 1_700_000_000;
+
+    #[test]
+    fn known_vector() {
+        // python: hmac.new(b"livebox-secret", b'1700000000.{"type":"chat"}', hashlib.sha256).hexdigest()
+        assert_eq!(
+            sign(SECRET, TS, BODY),
+            "sha256=157648e1ba769817350eebc0fadd0c21ae72da6fa2587d688286824a3494246a"
+        );
+    }
+
+    #[test]
+    fn verify_roundtrip() {
+        let signature = sign(SECRET, TS, BODY);
+        let result = verify(
+            SECRET,
+            "1700000000",
+            &signature,
+            BODY,
+            TS + 10,
+            DEFAULT_TOLERANCE_SECS,
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn rejects_tampered_body() {
+        let signature = sign(SECRET, TS, BODY);
+        let result = verify(
+            SECRET,
+            "1700000000",
+            &signature,
+            br#"{"type":"gift"}"#,
+            TS,
+            DEFAULT_TOLERANCE_SECS,
+        );
+        assert_eq!(result, Err(VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        let signature = sign("other-secret", TS, BODY);
+        let result = verify(
+            SECRET,
+            "1700000000",
+            &signature,
+            BODY,
+            TS,
+            DEFAULT_TOLERANCE_SECS,
+        );
+        assert_eq!(result, Err(VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn rejects_bad_timestamp() {
+        let signature = sign(SECRET, TS, BODY);
+        let expired = verify(SECRET, "1700000000", &signature, BODY, TS + 301, 300);
+        assert_eq!(expired, Err(VerifyError::Expired));
+        let invalid = verify(SECRET, "abc", &signature, BODY, TS, 300);
+        assert_eq!(invalid, Err(VerifyError::InvalidTimestamp));
+        // 时间戳也在签名范围内，改了就对不上
+        let moved = verify(SECRET, "1700000001", &signature, BODY, TS, 300);
+        assert_eq!(moved, Err(VerifyError::InvalidSignature));
+    }
+}
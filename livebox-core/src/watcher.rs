@@ -0,0 +1,413 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Duration;
+
+use crate::model::LiveInfo;
+use crate::resolver;
+use crate::room::RoomStatus;
+use crate::runner::DouYinReq;
+use crate::signature;
+use crate::utils::http::shared_client;
+use crate::utils::paths::livebox_dir;
+use crate::utils::time::now_millis;
+// 开播通知和消息推送带同样的签名请求头，接收端可以用同一套代码校验
+#[cfg(feature = "forwarding")]
+use crate::utils::webhook_sign::sign_request;
+use crate::webcast::PushParams;
+
+// 默认一分钟检查一次，最短 15 秒，太频繁容易触发验证码
+pub const DEFAULT_INTERVAL_SECS: u64 = 60;
+pub const MIN_INTERVAL_SECS: u64 = 15;
+
+// 每次开始关注分配一个编号，取消后马上重新关注时旧的检查结果不会写到新的记录上
+static WATCH_SEQ: AtomicU64 = AtomicU64::new(0);
+
+// 关注的直播间，保存在 ~/.livebox/watchlist.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchTarget {
+    pub web_rid: String,
+    // live.douyin.com/<web_rid>
+    pub url: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    // 开播时自动开始监听弹幕
+    #[serde(default)]
+    pub auto_start: bool,
+    #[serde(default)]
+    pub added_at: u64,
+}
+
+// 保存到文件的内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchConfig {
+    #[serde(default)]
+    pub rooms: Vec<WatchTarget>,
+    // 开播、下播时把 WatchEvent POST 到这个地址
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+// 关注的直播间和最近一次检查的结果，给界面显示
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchInfo {
+    #[serde(flatten)]
+    pub target: WatchTarget,
+    // 还没有检查过时为空
+    pub status: Option<RoomStatus>,
+    pub room_id: String,
+    pub title: String,
+    pub anchor_name: String,
+    pub checked_at: u64,
+    // 最近一次检查失败的原因，成功后清空
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchChange {
+    GoLive,
+    GoOffline,
+}
+
+// 开播或者下播
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchEvent {
+    pub change: WatchChange,
+    pub room: WatchInfo,
+    pub timestamp: u64,
+    // 开播时直接用来连接弹幕，不用再请求一次直播间页面
+    #[serde(skip)]
+    pub params: Option<PushParams>,
+}
+
+struct Entry {
+    id: u64,
+    info: WatchInfo,
+    // 移除关注时丢掉这个 Sender，检查任务随之退出
+    _stop: oneshot::Sender<()>,
+}
+
+// 定时检查关注的直播间是否开播，状态变化时通过通道发出 WatchEvent
+#[derive(Clone)]
+pub struct Watcher {
+    rooms: Arc<Mutex<HashMap<String, Entry>>>,
+    webhook: Arc<Mutex<Option<String>>>,
+    // 开播通知的签名密钥，和消息推送用同一个，只保存在内存里
+    secret: Arc<Mutex<Option<String>>>,
+    saved: Arc<Mutex<Vec<WatchTarget>>>,
+    tx: mpsc::UnboundedSender<WatchEvent>,
+    path: PathBuf,
+}
+
+impl Watcher {
+    /// 使用默认的 ~/.livebox/watchlist.json
+    pub fn open_default() -> (Self, mpsc::UnboundedReceiver<WatchEvent>) {
+        Self::open(livebox_dir().join("watchlist.json"))
+    }
+
+    /// 读取保存的关注列表，调用 start 之后才开始检查；文件损坏时忽略，从空列表开始
+    pub fn open(path: PathBuf) -> (Self, mpsc::UnboundedReceiver<WatchEvent>) {
+        let config: WatchConfig = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("⚠️ [关注] 关注列表解析失败，已忽略: {}", e);
+                WatchConfig::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => WatchConfig::default(),
+            Err(e) => {
+                eprintln!("⚠️ [关注] 读取关注列表失败，已忽略: {}", e);
+                WatchConfig::default()
+            }
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher = Watcher {
+            rooms: Arc::new(Mutex::new(HashMap::new())),
+            webhook: Arc::new(Mutex::new(config.webhook)),
+            secret: Arc::new(Mutex::new(None)),
+            saved: Arc::new(Mutex::new(config.rooms)),
+            tx,
+            path,
+        };
+        (watcher, rx)
+    }
+
+    /// 开始检查保存的直播间，需要在 tokio 运行时里调用
+    pub fn start(&self) {
+        let saved = self
+            .saved
+            .lock()
+            .map(|mut saved| std::mem::take(&mut *saved))
+            .unwrap_or_default();
+        for target in saved {
+            self.insert(target);
+        }
+    }
+
+    /// 关注一个直播间，输入可以是直播间号、地址或者短链接；已经关注时更新设置
+    pub async fn add(
+        &self,
+        input: &str,
+        interval_secs: Option<u64>,
+        auto_start: bool,
+    ) -> Result<WatchInfo, String> {
+        let resolved = resolver::resolve_room(input)
+            .await
+            .map_err(|e| format!("解析直播间地址失败: {}", e))?;
+        let interval_secs = interval_secs
+            .unwrap_or(DEFAULT_INTERVAL_SECS)
+            .max(MIN_INTERVAL_SECS);
+
+        let updated = self.rooms.lock().ok().and_then(|mut rooms| {
+            let entry = rooms.get_mut(&resolved.web_rid)?;
+            entry.info.target.interval_secs = interval_secs;
+            entry.info.target.auto_start = auto_start;
+            Some(entry.info.clone())
+        });
+        let info = match updated {
+            Some(info) => info,
+            None => self.insert(WatchTarget {
+                web_rid: resolved.web_rid,
+                url: resolved.url,
+                interval_secs,
+                auto_start,
+                added_at: now_millis(),
+            }),
+        };
+        self.save();
+        Ok(info)
+    }
+
+    /// 取消关注，返回之前是否关注了这个直播间
+    pub fn remove(&self, web_rid: &str) -> bool {
+        let removed = self
+            .rooms
+            .lock()
+            .map(|mut rooms| rooms.remove(web_rid).is_some())
+            .unwrap_or(false);
+        if removed {
+            eprintln!("👋 [关注] 取消关注 {}", web_rid);
+            self.save();
+        }
+        removed
+    }
+
+    /// 关注的直播间，按关注时间排序
+    pub fn list(&self) -> Vec<WatchInfo> {
+        let mut list: Vec<WatchInfo> = self
+            .rooms
+            .lock()
+            .map(|rooms| rooms.values().map(|e| e.info.clone()).collect())
+            .unwrap_or_default();
+        list.sort_by_key(|info| info.target.added_at);
+        list
+    }
+
+    pub fn webhook(&self) -> Option<String> {
+        self.webhook.lock().ok().and_then(|w| w.clone())
+    }
+
+    pub fn set_webhook(&self, url: Option<String>) {
+        let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+        eprintln!(
+            "📮 [关注] 开播通知地址: {}",
+            url.as_deref().unwrap_or("(未设置)")
+        );
+        if let Ok(mut webhook) = self.webhook.lock() {
+            *webhook = url;
+        }
+        self.save();
+    }
+
+    /// 设置开播通知的签名密钥，为空时不签名
+    pub fn set_webhook_secret(&self, secret: Option<String>) {
+        if let Ok(mut current) = self.secret.lock() {
+            *current = secret.filter(|s| !s.is_empty());
+        }
+    }
+
+    fn insert(&self, target: WatchTarget) -> WatchInfo {
+        let web_rid = target.web_rid.clone();
+        let info = WatchInfo {
+            target,
+            status: None,
+            room_id: String::new(),
+            title: String::new(),
+            anchor_name: String::new(),
+            checked_at: 0,
+            error: None,
+        };
+        let id = WATCH_SEQ.fetch_add(1, Ordering::Relaxed);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        if let Ok(mut rooms) = self.rooms.lock() {
+            rooms.insert(
+                web_rid.clone(),
+                Entry {
+                    id,
+                    info: info.clone(),
+                    _stop: stop_tx,
+                },
+            );
+        }
+        eprintln!("⭐ [关注] 开始关注 {}", web_rid);
+        tokio::spawn(self.clone().watch(web_rid, id, stop_rx));
+        info
+    }
+
+    // 定时检查一个直播间，取消关注后退出
+    async fn watch(self, web_rid: String, id: u64, mut stop: oneshot::Receiver<()>) {
+        // 同时关注很多直播间时错开第一次检查
+        let mut delay = Duration::from_millis(rand::thread_rng().gen_range(0..3000));
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = &mut stop => return,
+            }
+            let Some(target) = self.target(&web_rid, id) else {
+                return;
+            };
+            let result = DouYinReq::new(&target.url)
+                .get_room_info()
+                .await
+                .map_err(|e| e.to_string());
+            if let Some(event) = self.update(&web_rid, id, result) {
+                eprintln!(
+                    "📣 [关注] {} {}",
+                    event.room.anchor_name,
+                    event.room.status.unwrap_or_default().label()
+                );
+                if let Some(url) = self.webhook() {
+                    let secret = self.secret.lock().ok().and_then(|s| s.clone());
+                    tokio::spawn(post_webhook(url, secret, event.clone()));
+                }
+                let _ = self.tx.send(event);
+            }
+            delay = jittered(target.interval_secs);
+        }
+    }
+
+    fn target(&self, web_rid: &str, id: u64) -> Option<WatchTarget> {
+        let rooms = self.rooms.lock().ok()?;
+        let entry = rooms.get(web_rid).filter(|e| e.id == id)?;
+        Some(entry.info.target.clone())
+    }
+
+    // 记录检查结果，开播或下播时返回事件；第一次检查时已经在直播也算开播
+    fn update(
+        &self,
+        web_rid: &str,
+        id: u64,
+        result: Result<LiveInfo, String>,
+    ) -> Option<WatchEvent> {
+        let mut rooms = self.rooms.lock().ok()?;
+        let info = &mut rooms.get_mut(web_rid).filter(|e| e.id == id)?.info;
+        info.checked_at = now_millis();
+        let live = match result {
+            Ok(live) => live,
+            Err(e) => {
+                eprintln!("⚠️ [关注] 检查 {} 失败: {}", web_rid, e);
+                info.error = Some(e);
+                return None;
+            }
+        };
+
+        let was_live = info.status.is_some_and(RoomStatus::is_live);
+        info.status = Some(live.status);
+        info.error = None;
+        if !live.room.room_id.is_empty() {
+            info.room_id = live.room.room_id.clone();
+        }
+        if !live.room.title.is_empty() {
+            info.title = live.room.title.clone();
+        }
+        if !live.room.owner.nickname.is_empty() {
+            info.anchor_name = live.room.owner.nickname.clone();
+        }
+
+        let change = match (was_live, live.status.is_live()) {
+            (false, true) => WatchChange::GoLive,
+            (true, false) => WatchChange::GoOffline,
+            _ => return None,
+        };
+        let params = (change == WatchChange::GoLive).then(|| PushParams {
+            room_id: live.room.room_id.clone(),
+            signature: signature::creat_signature(&live.room.room_id, &live.unique_id),
+            unique_id: live.unique_id,
+            ttwid: live.ttwid,
+            room_url: Some(info.target.url.clone()),
+        });
+        Some(WatchEvent {
+            change,
+            room: info.clone(),
+            timestamp: now_millis(),
+            params,
+        })
+    }
+
+    fn save(&self) {
+        let mut rooms: Vec<WatchTarget> = self
+            .rooms
+            .lock()
+            .map(|rooms| rooms.values().map(|e| e.info.target.clone()).collect())
+            .unwrap_or_default();
+        rooms.sort_by_key(|target| target.added_at);
+        let config = WatchConfig {
+            rooms,
+            webhook: self.webhook(),
+        };
+        if let Err(e) = write_config(&self.path, &config) {
+            eprintln!("❌ [关注] 保存关注列表失败: {}", e);
+        }
+    }
+}
+
+fn write_config(path: &Path, config: &WatchConfig) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(config)?)
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL_SECS
+}
+
+// 检查间隔上下浮动 20%，避免请求太规律
+fn jittered(interval_secs: u64) -> Duration {
+    let millis = interval_secs.max(MIN_INTERVAL_SECS) * 1000;
+    let spread = millis / 5;
+    Duration::from_millis(millis - spread + rand::thread_rng().gen_range(0..=spread * 2))
+}
+
+async fn post_webhook(url: String, secret: Option<String>, event: WatchEvent) {
+    let body = match serde_json::to_vec(&event) {
+        Ok(body) => body,
+        Err(e) => {
+            eprintln!("⚠️ [关注] 开播通知序列化失败: {}", e);
+            return;
+        }
+    };
+    let request = shared_client()
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    let request = sign_request(request, secret.as_deref(), &body);
+    match request.body(body).send().await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => eprintln!("⚠️ [关注] 开播通知推送失败: {} {}", url, response.status()),
+        Err(e) => eprintln!("⚠️ [关注] 开播通知推送失败: {} {}", url, e),
+    }
+}
+
+// 没有推送功能时没有签名模块，开播通知不签名
+#[cfg(not(feature = "forwarding"))]
+fn sign_request(
+    request: reqwest::RequestBuilder,
+    _secret: Option<&str>,
+    _body: &[u8],
+) -> reqwest::RequestBuilder {
+    request
+}
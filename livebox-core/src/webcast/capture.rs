@@ -0,0 +1,199 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::paths::livebox_dir;
+
+// 抓包文件格式：
+// 文件头 = MAGIC + room_id 长度(u16 LE) + room_id
+// 每一帧 = 收到的时间(毫秒, u64 LE) + 长度(u32 LE) + WebSocket 二进制原始数据
+const MAGIC: &[u8; 8] = b"LBCAP\x00\x00\x01";
+
+/// 默认抓包位置 ~/.livebox/captures/{room_id}-{毫秒时间戳}.lbcap
+pub fn default_capture_path(room_id: &str, now_millis: u64) -> PathBuf {
+    livebox_dir()
+        .join("captures")
+        .join(format!("{}-{}.lbcap", room_id, now_millis))
+}
+
+// 把收到的每一帧原始数据写入抓包文件
+pub struct FrameRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl FrameRecorder {
+    pub fn create(path: &Path, room_id: &str) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(room_id.len() as u16).to_le_bytes())?;
+        writer.write_all(room_id.as_bytes())?;
+        writer.flush()?;
+        eprintln!("📼 [抓包] 开始录制原始数据: {:?}", path);
+        Ok(FrameRecorder {
+            path: path.to_path_buf(),
+            writer,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 写入一帧，每帧都立即落盘，程序崩溃时也能保留崩溃前的数据
+    pub fn write_frame(&mut self, received_at: u64, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&received_at.to_le_bytes())?;
+        self.writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.flush()
+    }
+}
+
+// 抓包文件中的一帧
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub received_at: u64,
+    pub data: Vec<u8>,
+}
+
+// 按顺序读取抓包文件里的帧
+pub struct FrameReader {
+    room_id: String,
+    reader: BufReader<File>,
+}
+
+impl FrameReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic[..5] != MAGIC[..5] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "不是 LiveBox 抓包文件"));
+        }
+        // 后 3 个字节是格式版本
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "不支持的抓包文件版本"));
+        }
+        let mut len = [0u8; 2];
+        reader.read_exact(&mut len)?;
+        let mut room_id = vec![0u8; u16::from_le_bytes(len) as usize];
+        reader.read_exact(&mut room_id)?;
+        Ok(FrameReader {
+            room_id: String::from_utf8_lossy(&room_id).to_string(),
+            reader,
+        })
+    }
+
+    /// 录制时的房间号
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+
+    /// 读取下一帧，文件结束时返回 None；最后一帧写到一半（程序中途退出）也当作结束
+    pub fn next_frame(&mut self) -> io::Result<Option<CapturedFrame>> {
+        let mut header = [0u8; 12];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let received_at = u64::from_le_bytes(header[..8].try_into().unwrap_or_default());
+        let len = u32::from_le_bytes(header[8..].try_into().unwrap_or_default()) as usize;
+        let mut data = vec![0u8; len];
+        match self.reader.read_exact(&mut data) {
+            Ok(()) => Ok(Some(CapturedFrame { received_at, data })),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::now_millis;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("livebox-capture-{}-{}", name, now_millis()))
+            .join("test.lbcap")
+    }
+
+    fn read_all(path: &Path) -> (String, Vec<(u64, Vec<u8>)>) {
+        let mut reader = FrameReader::open(path).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.next_frame().unwrap() {
+            frames.push((frame.received_at, frame.data));
+        }
+        (reader.room_id().to_string(), frames)
+    }
+
+    fn frames() -> Vec<(u64, Vec<u8>)> {
+        vec![
+            (1_700_000_000_000, b"\x08\x01first".to_vec()),
+            (1_700_000_000_050, Vec::new()),
+            (1_700_000_001_000, vec![0xff; 4096]),
+        ]
+    }
+
+    fn record(path: &Path) {
+        let mut recorder = FrameRecorder::create(path, "7392840123456789").unwrap();
+        for (received_at, data) in frames() {
+            recorder.write_frame(received_at, &data).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        record(&path);
+        let (room_id, read) = read_all(&path);
+        assert_eq!(room_id, "7392840123456789");
+        assert_eq!(read, frames());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn truncated_last_frame() {
+        let path = temp_path("truncated");
+        record(&path);
+        let full = fs::read(&path).unwrap();
+        let expected = &frames()[..2];
+
+        // 最后一帧的数据写到一半
+        fs::write(&path, &full[..full.len() - 100]).unwrap();
+        assert_eq!(read_all(&path).1, expected);
+        // 最后一帧的帧头只写了一部分
+        fs::write(&path, &full[..full.len() - 4096 - 5]).unwrap();
+        assert_eq!(read_all(&path).1, expected);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let path = temp_path("bad-header");
+        record(&path);
+        let full = fs::read(&path).unwrap();
+
+        let mut wrong_magic = full.clone();
+        wrong_magic[..5].copy_from_slice(b"NOCAP");
+        fs::write(&path, &wrong_magic).unwrap();
+        let err = FrameReader::open(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut wrong_version = full;
+        wrong_version[7] = 2;
+        fs::write(&path, &wrong_version).unwrap();
+        let err = FrameReader::open(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "不支持的抓包文件版本");
+
+        // 文件头都不完整
+        fs::write(&path, b"LBCAP").unwrap();
+        assert!(FrameReader::open(&path).is_err());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,96 @@
+pub mod capture;
+pub mod monitor;
+mod polling;
+pub mod socket;
+
+// 弹幕 WebSocket 服务地址
+pub const PUSH_SERVER: &str = "wss://webcast5-ws-web-lf.douyin.com/webcast/im/push/v2/";
+
+// WebSocket 连不上时用来轮询弹幕的 HTTP 地址
+pub const FETCH_SERVER: &str = "https://live.douyin.com/webcast/im/fetch/";
+
+// 连接弹幕服务时使用的浏览器标识，和前端保持一致
+pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0";
+
+// 建立弹幕连接需要的参数，都来自 get_live_html 返回的 LiveInfo
+#[derive(Debug, Clone)]
+pub struct PushParams {
+    pub room_id: String,
+    pub unique_id: String,
+    pub ttwid: String,
+    pub signature: String,
+    // 直播间页面地址，重连时参数被拒绝就用它重新获取 LiveInfo，为空时不刷新
+    pub room_url: Option<String>,
+}
+
+// Response 里用来续传的字段，重连时带上，服务器会从上次的位置继续推送
+#[derive(Debug, Clone, Default)]
+pub struct PushCursor {
+    pub cursor: String,
+    pub internal_ext: String,
+    // 服务器建议的拉取间隔（毫秒）
+    pub fetch_interval: u64,
+}
+
+impl PushCursor {
+    pub fn is_empty(&self) -> bool {
+        self.cursor.is_empty() && self.internal_ext.is_empty()
+    }
+}
+
+impl PushParams {
+    /// 组装 webcast/im/push/v2 的连接地址，resume 不为空时从上次的位置继续
+    pub fn push_url(&self, resume: &PushCursor) -> String {
+        let query = self.query(
+            resume,
+            &[
+                ("compress", "gzip"),
+                ("signature", self.signature.as_str()),
+            ],
+        );
+        format!("{}?{}", PUSH_SERVER, query)
+    }
+
+    /// 组装 webcast/im/fetch 的轮询地址，参数和续传位置都和 WebSocket 一样
+    pub fn fetch_url(&self, resume: &PushCursor) -> String {
+        let query = self.query(
+            resume,
+            &[
+                ("app_name", "douyin_web"),
+                ("resp_content_type", "protobuf"),
+                ("fetch_rule", "1"),
+                ("last_rtt", "0"),
+            ],
+        );
+        format!("{}?{}", FETCH_SERVER, query)
+    }
+
+    fn query(&self, resume: &PushCursor, extra: &[(&str, &str)]) -> String {
+        let browser_version = USER_AGENT.trim_start_matches("Mozilla/");
+        let mut params = vec![
+            ("room_id", self.room_id.as_str()),
+            ("version_code", "180800"),
+            ("webcast_sdk_version", "1.0.14-beta.0"),
+            ("live_id", "1"),
+            ("did_rule", "3"),
+            ("user_unique_id", self.unique_id.as_str()),
+            ("identity", "audience"),
+            ("aid", "6383"),
+            ("device_platform", "web"),
+            ("browser_language", "zh-CN"),
+            ("browser_platform", "Win32"),
+            ("browser_name", "Mozilla"),
+            ("browser_version", browser_version),
+        ];
+        params.extend_from_slice(extra);
+        if !resume.is_empty() {
+            params.push(("cursor", resume.cursor.as_str()));
+            params.push(("internal_ext", resume.internal_ext.as_str()));
+        }
+        params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
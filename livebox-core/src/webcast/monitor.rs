@@ -0,0 +1,223 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use super::capture::{FrameReader, FrameRecorder};
+use super::socket::{ConnectionState, LiveSocket, SocketEvent};
+use super::PushParams;
+use crate::utils::time::now_millis;
+
+// 每次启动监听分配一个编号，用来区分同一个房间前后两次的连接
+static MONITOR_SEQ: AtomicU64 = AtomicU64::new(0);
+
+// 直播间的基本信息，启动监听时由调用方传入
+#[derive(Debug, Clone, Default)]
+pub struct MonitorMeta {
+    pub url: String,
+    pub title: String,
+    pub anchor_name: String,
+}
+
+// 正在监听的直播间，给界面显示
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub room_id: String,
+    pub url: String,
+    pub title: String,
+    pub anchor_name: String,
+    pub status: ConnectionState,
+    pub started_at: u64,
+    pub replay: bool,
+    pub capture_path: Option<String>,
+}
+
+// 带上房间号的连接事件
+#[derive(Debug)]
+pub struct RoomEvent {
+    pub room_id: String,
+    pub event: SocketEvent,
+}
+
+struct Monitor {
+    id: u64,
+    info: MonitorInfo,
+    socket: LiveSocket,
+}
+
+// 同时监听多个直播间，按房间号管理，所有房间的事件合并到一个通道里
+#[derive(Clone)]
+pub struct MonitorManager {
+    monitors: Arc<Mutex<HashMap<String, Monitor>>>,
+    tx: mpsc::UnboundedSender<RoomEvent>,
+}
+
+impl MonitorManager {
+    /// 创建管理器，返回的 receiver 收到所有房间的事件
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<RoomEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let manager = MonitorManager {
+            monitors: Arc::new(Mutex::new(HashMap::new())),
+            tx,
+        };
+        (manager, rx)
+    }
+
+    /// 开始监听直播间，这个房间已经在监听时先断开旧连接
+    pub fn start(
+        &self,
+        params: PushParams,
+        meta: MonitorMeta,
+        recorder: Option<FrameRecorder>,
+    ) -> MonitorInfo {
+        let room_id = params.room_id.clone();
+        let capture_path = recorder
+            .as_ref()
+            .map(|r| r.path().to_string_lossy().to_string());
+        self.insert(&room_id, meta, false, capture_path, |tx| {
+            LiveSocket::connect(params, recorder, tx)
+        })
+    }
+
+    /// 回放抓包文件，房间号取自抓包文件
+    pub fn replay(&self, path: PathBuf, speed: f64) -> std::io::Result<MonitorInfo> {
+        let room_id = FrameReader::open(&path)?.room_id().to_string();
+        let meta = MonitorMeta {
+            url: path.to_string_lossy().to_string(),
+            title: "回放".to_string(),
+            anchor_name: String::new(),
+        };
+        Ok(self.insert(&room_id, meta, true, None, |tx| {
+            LiveSocket::replay(path, speed, tx)
+        }))
+    }
+
+    /// 停止监听直播间，返回这个房间之前是否在监听
+    pub fn stop(&self, room_id: &str) -> bool {
+        let removed = self
+            .monitors
+            .lock()
+            .ok()
+            .and_then(|mut monitors| monitors.remove(room_id));
+        match removed {
+            Some(mut monitor) => {
+                monitor.socket.close();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 停止所有直播间，返回停止的数量
+    pub fn stop_all(&self) -> usize {
+        let removed: Vec<Monitor> = self
+            .monitors
+            .lock()
+            .map(|mut monitors| monitors.drain().map(|(_, m)| m).collect())
+            .unwrap_or_default();
+        let count = removed.len();
+        for mut monitor in removed {
+            monitor.socket.close();
+        }
+        count
+    }
+
+    /// 所有直播间的状态，按开始时间排序
+    pub fn list(&self) -> Vec<MonitorInfo> {
+        let mut list: Vec<MonitorInfo> = self
+            .monitors
+            .lock()
+            .map(|monitors| monitors.values().map(|m| m.info.clone()).collect())
+            .unwrap_or_default();
+        list.sort_by_key(|info| info.started_at);
+        list
+    }
+
+    pub fn get(&self, room_id: &str) -> Option<MonitorInfo> {
+        let monitors = self.monitors.lock().ok()?;
+        monitors.get(room_id).map(|m| m.info.clone())
+    }
+
+    fn insert(
+        &self,
+        room_id: &str,
+        meta: MonitorMeta,
+        replay: bool,
+        capture_path: Option<String>,
+        connect: impl FnOnce(mpsc::UnboundedSender<SocketEvent>) -> LiveSocket,
+    ) -> MonitorInfo {
+        let id = MONITOR_SEQ.fetch_add(1, Ordering::Relaxed);
+        let info = MonitorInfo {
+            room_id: room_id.to_string(),
+            url: meta.url,
+            title: meta.title,
+            anchor_name: meta.anchor_name,
+            status: ConnectionState::Connecting,
+            started_at: now_millis(),
+            replay,
+            capture_path,
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let socket = connect(tx);
+        let old = self.monitors.lock().ok().and_then(|mut monitors| {
+            monitors.insert(
+                room_id.to_string(),
+                Monitor {
+                    id,
+                    info: info.clone(),
+                    socket,
+                },
+            )
+        });
+        if let Some(mut old) = old {
+            eprintln!("🔁 [监听] 房间 {} 重新开始监听", room_id);
+            old.socket.close();
+        }
+        tokio::spawn(self.clone().relay(room_id.to_string(), id, rx));
+        eprintln!("👀 [监听] 开始监听房间 {}", room_id);
+        info
+    }
+
+    // 把一个连接的事件带上房间号转发到合并的通道，同时更新房间状态
+    async fn relay(self, room_id: String, id: u64, mut rx: mpsc::UnboundedReceiver<SocketEvent>) {
+        while let Some(event) = rx.recv().await {
+            let current = match &event {
+                SocketEvent::State(state) => self.update(&room_id, id, state.clone()),
+                SocketEvent::Message(_) | SocketEvent::Gap(_) => self.is_current(&room_id, id),
+            };
+            // 同一个房间已经重新开始监听时，旧连接剩下的事件直接丢弃
+            if !current {
+                continue;
+            }
+            let _ = self.tx.send(RoomEvent {
+                room_id: room_id.clone(),
+                event,
+            });
+        }
+    }
+
+    // 这个连接是否还是房间当前的连接；房间已经停止监听时也算，让断开事件能发出去
+    fn is_current(&self, room_id: &str, id: u64) -> bool {
+        self.monitors
+            .lock()
+            .map(|monitors| monitors.get(room_id).is_none_or(|m| m.id == id))
+            .unwrap_or(false)
+    }
+
+    fn update(&self, room_id: &str, id: u64, status: ConnectionState) -> bool {
+        let Ok(mut monitors) = self.monitors.lock() else {
+            return false;
+        };
+        match monitors.get_mut(room_id) {
+            Some(monitor) if monitor.id == id => {
+                monitor.info.status = status;
+                true
+            }
+            Some(_) => false,
+            None => true,
+        }
+    }
+}
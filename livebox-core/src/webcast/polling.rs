@@ -0,0 +1,120 @@
+// HTTP 轮询：有些网络屏蔽了 WSS，但 HTTPS 可以访问，这时改用 webcast/im/fetch 拉取弹幕
+// 拉到的数据包装成和 WebSocket 一样的 PushFrame，解码、去重、录制都走同一套流程
+use prost::Message as _;
+use reqwest::header::{COOKIE, REFERER, USER_AGENT as USER_AGENT_HEADER};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Duration;
+
+use super::capture::FrameRecorder;
+use super::socket::{handle_frame, ConnectionState, Disconnect, SocketEvent, StreamState};
+use super::{PushParams, USER_AGENT};
+use crate::proto::PushFrame;
+use crate::utils::time::now_millis;
+
+// 服务器没有给出拉取间隔时默认 1 秒拉一次
+const DEFAULT_FETCH_INTERVAL: u64 = 1000;
+// 拉取间隔的上下限，避免服务器返回异常值时拉得太快或太慢
+const MIN_FETCH_INTERVAL: u64 = 500;
+const MAX_FETCH_INTERVAL: u64 = 10_000;
+// 单次请求的超时时间
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 按服务器给出的间隔不停拉取，直到主动断开或请求失败
+pub(super) async fn poll(
+    params: &PushParams,
+    recorder: &mut Option<FrameRecorder>,
+    stream: &mut StreamState,
+    tx: &mpsc::UnboundedSender<SocketEvent>,
+    stop: &mut oneshot::Receiver<()>,
+) -> Disconnect {
+    eprintln!("🔌 [轮询] 开始拉取弹幕，房间号: {}", params.room_id);
+    let lost = |reason: String, rejected: bool, connected: bool, received: bool| {
+        Disconnect::Lost {
+            reason,
+            rejected,
+            connected,
+            received,
+        }
+    };
+    let client = match reqwest::Client::builder().timeout(FETCH_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return lost(format!("创建 HTTP 客户端失败: {}", e), false, false, false),
+    };
+    let referer = params
+        .room_url
+        .clone()
+        .unwrap_or_else(|| "https://live.douyin.com/".to_string());
+
+    let mut received = false;
+    loop {
+        let mut request = client
+            .get(params.fetch_url(&stream.cursor))
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .header(REFERER, referer.as_str());
+        if !params.ttwid.is_empty() {
+            request = request.header(COOKIE, format!("ttwid={}", params.ttwid));
+        }
+        let response = tokio::select! {
+            _ = &mut *stop => return Disconnect::Stopped,
+            response = request.send() => response,
+        };
+        let body = match response {
+            Ok(response) if response.status().is_success() => match response.bytes().await {
+                Ok(body) => body,
+                Err(e) => return lost(format!("读取轮询结果失败: {}", e), false, received, received),
+            },
+            // 4xx 说明参数不被接受，需要重新获取直播间信息
+            Ok(response) => {
+                let status = response.status();
+                return lost(
+                    format!("轮询被拒绝: {}", status),
+                    status.is_client_error(),
+                    received,
+                    received,
+                );
+            }
+            Err(e) => return lost(format!("轮询失败: {}", e), false, received, received),
+        };
+
+        // im/fetch 返回的是没有压缩的 Response，包装成 PushFrame 后和 WebSocket 收到的数据一样处理
+        let frame = PushFrame {
+            payload_type: "msg".to_string(),
+            payload_encoding: "none".to_string(),
+            payload: body.to_vec(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        if let Some(rec) = recorder.as_mut() {
+            if let Err(e) = rec.write_frame(now_millis(), &frame) {
+                eprintln!("⚠️ [抓包] 写入失败，停止录制 {:?}: {}", rec.path(), e);
+                *recorder = None;
+            }
+        }
+        match handle_frame(&frame, stream, tx) {
+            Ok(handled) => {
+                if !received {
+                    eprintln!("✅ [轮询] 已开始接收弹幕");
+                    let _ = tx.send(SocketEvent::State(ConnectionState::Live));
+                    received = true;
+                }
+                if handled.live_ended {
+                    return Disconnect::LiveEnded;
+                }
+            }
+            // 第一次就解不出来，多半是返回了错误页面
+            Err(e) if !received => {
+                return lost(format!("轮询结果无法解码: {}", e), true, false, false)
+            }
+            Err(e) => eprintln!("⚠️ [轮询] 解码失败: {}", e),
+        }
+
+        let interval = match stream.cursor.fetch_interval {
+            0 => DEFAULT_FETCH_INTERVAL,
+            interval => interval.clamp(MIN_FETCH_INTERVAL, MAX_FETCH_INTERVAL),
+        };
+        tokio::select! {
+            _ = &mut *stop => return Disconnect::Stopped,
+            _ = tokio::time::sleep(Duration::from_millis(interval)) => {}
+        }
+    }
+}
@@ -0,0 +1,759 @@
+use futures_util::{SinkExt, StreamExt};
+use prost::Message as _;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval_at, Duration, Instant};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+
+use super::capture::{FrameReader, FrameRecorder};
+use super::polling;
+use super::{PushCursor, PushParams, USER_AGENT};
+use crate::proto::{self, douyin, PushFrame};
+use crate::resolver::{self, RoomLink};
+use crate::runner::DouYinReq;
+use crate::signature;
+use crate::utils::time::now_millis;
+
+// 心跳间隔，和前端 SocketCli 的默认值一致
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+// 超过这个时间没有收到任何数据就认为连接已经断了
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+// 重连等待时间从 1 秒开始翻倍，最多等 60 秒
+const RECONNECT_BASE: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+// 连续重连失败这么多次后放弃
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+// WebSocket 连续握手失败这么多次后改用 HTTP 轮询
+const HANDSHAKE_FAILURES_BEFORE_POLLING: u32 = 3;
+// ControlMessage 的 status 为 3 表示直播结束
+const CONTROL_STATUS_ENDED: i32 = 3;
+// 用来去重的最近消息 id 数量
+const RECENT_ID_CAPACITY: usize = 5000;
+
+// 连接状态，重连过程中会多次变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    // 正在建立连接
+    Connecting,
+    // 已连上，正在接收消息
+    Live,
+    // 连接断开，等待 delay_ms 毫秒后进行第 attempt 次重连
+    Reconnecting {
+        attempt: u32,
+        delay_ms: u64,
+        reason: String,
+    },
+    // 不会再重连：主动断开、直播结束或重连次数用完
+    Ended { reason: String },
+}
+
+// 重连前后可能漏掉消息的时间段，毫秒时间戳
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageGap {
+    // 断线前最后一次收到消息的时间
+    pub from: u64,
+    // 重连后第一次收到消息的时间
+    pub to: u64,
+    // 断线原因
+    pub reason: String,
+}
+
+// 连接过程中产生的事件，由调用方决定如何处理（发给前端、写入文件等）
+#[derive(Debug)]
+pub enum SocketEvent {
+    // 连接状态变化，Ended 一定是最后一个事件
+    State(ConnectionState),
+    // 收到一条弹幕消息（messagesList 中的一项），重复的消息已经去掉
+    Message(proto::Message),
+    // 重连后没能确认消息是连续的，这段时间内可能漏了消息
+    Gap(MessageGap),
+}
+
+// 弹幕 WebSocket 客户端，创建后在后台任务中收发数据，断线后自动重连，drop 时自动断开
+// 也可以用来回放抓包文件，回放时产生的事件和真实连接完全一样
+pub struct LiveSocket {
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl LiveSocket {
+    /// 建立连接，收到的消息通过 tx 发送出去；传入 recorder 时把每一帧原始数据写入抓包文件
+    pub fn connect(
+        params: PushParams,
+        recorder: Option<FrameRecorder>,
+        tx: mpsc::UnboundedSender<SocketEvent>,
+    ) -> Self {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let reason = run(params, recorder, &tx, stop_rx).await;
+            eprintln!("🔌 [WebSocket] 连接已结束: {}", reason);
+            let _ = tx.send(SocketEvent::State(ConnectionState::Ended { reason }));
+        });
+        LiveSocket {
+            stop: Some(stop_tx),
+        }
+    }
+
+    /// 回放抓包文件，speed 为回放倍速（1.0 为原速），小于等于 0 时不等待，尽快回放完
+    pub fn replay(path: PathBuf, speed: f64, tx: mpsc::UnboundedSender<SocketEvent>) -> Self {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let reason = match replay(&path, speed, &tx, stop_rx).await {
+                Ok(reason) => reason,
+                Err(e) => format!("回放失败: {}", e),
+            };
+            eprintln!("📼 [回放] {}", reason);
+            let _ = tx.send(SocketEvent::State(ConnectionState::Ended { reason }));
+        });
+        LiveSocket {
+            stop: Some(stop_tx),
+        }
+    }
+
+    /// 主动断开连接
+    pub fn close(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+impl Drop for LiveSocket {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// 一次连接结束的原因
+pub(super) enum Disconnect {
+    // 调用方主动断开
+    Stopped,
+    // 收到直播结束的消息
+    LiveEnded,
+    // 连接意外断开，rejected 表示服务器不接受当前的参数，connected 表示是否连上过
+    Lost {
+        reason: String,
+        rejected: bool,
+        connected: bool,
+        received: bool,
+    },
+}
+
+// 收消息的方式，WebSocket 一直连不上时改用 HTTP 轮询
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    WebSocket,
+    Polling,
+}
+
+// 重新获取直播间信息的结果
+enum Refreshed {
+    Params(PushParams),
+    LiveEnded,
+}
+
+// 连接并在断线后重连，直到主动断开、直播结束或重连次数用完，返回结束原因
+async fn run(
+    mut params: PushParams,
+    mut recorder: Option<FrameRecorder>,
+    tx: &mpsc::UnboundedSender<SocketEvent>,
+    mut stop: oneshot::Receiver<()>,
+) -> String {
+    let mut attempt = 0u32;
+    let mut stream = StreamState::default();
+    let mut transport = Transport::WebSocket;
+    let mut handshake_failures = 0u32;
+    loop {
+        let _ = tx.send(SocketEvent::State(ConnectionState::Connecting));
+        let disconnect = match transport {
+            Transport::WebSocket => {
+                connect_once(&params, &mut recorder, &mut stream, tx, &mut stop).await
+            }
+            Transport::Polling => {
+                polling::poll(&params, &mut recorder, &mut stream, tx, &mut stop).await
+            }
+        };
+        let (reason, rejected) = match disconnect {
+            Disconnect::Stopped => return "主动断开".to_string(),
+            Disconnect::LiveEnded => return "直播已结束".to_string(),
+            Disconnect::Lost {
+                reason,
+                rejected,
+                connected,
+                received,
+            } => {
+                // 连上后正常收到过消息，重新开始计算重连次数
+                if received {
+                    attempt = 0;
+                }
+                // 握手总是失败但参数没被拒绝，多半是网络屏蔽了 WSS，改用 HTTP 轮询
+                if connected || rejected {
+                    handshake_failures = 0;
+                } else {
+                    handshake_failures += 1;
+                }
+                if transport == Transport::WebSocket
+                    && handshake_failures >= HANDSHAKE_FAILURES_BEFORE_POLLING
+                {
+                    eprintln!(
+                        "🔀 [WebSocket] 连续 {} 次握手失败，改用 HTTP 轮询",
+                        handshake_failures
+                    );
+                    transport = Transport::Polling;
+                }
+                stream.mark_gap(&reason);
+                (reason, rejected)
+            }
+        };
+
+        attempt += 1;
+        if attempt > MAX_RECONNECT_ATTEMPTS {
+            return format!("重连 {} 次都没有成功: {}", MAX_RECONNECT_ATTEMPTS, reason);
+        }
+        let delay = backoff_delay(attempt);
+        eprintln!(
+            "🔁 [WebSocket] 连接断开（{}），{} 毫秒后第 {} 次重连",
+            reason,
+            delay.as_millis(),
+            attempt
+        );
+        let _ = tx.send(SocketEvent::State(ConnectionState::Reconnecting {
+            attempt,
+            delay_ms: delay.as_millis() as u64,
+            reason,
+        }));
+        tokio::select! {
+            _ = &mut stop => return "主动断开".to_string(),
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        // 参数被拒绝时重新获取 ttwid、房间号和签名
+        if rejected {
+            if let Some(url) = params.room_url.clone() {
+                match refresh_params(&url, &params).await {
+                    Ok(Refreshed::Params(fresh)) => {
+                        // 换了房间号时旧的续传位置没有意义了
+                        if fresh.room_id != params.room_id {
+                            stream.cursor = PushCursor::default();
+                        }
+                        params = fresh;
+                    }
+                    Ok(Refreshed::LiveEnded) => return "直播已结束".to_string(),
+                    Err(e) => eprintln!("⚠️ [WebSocket] 刷新直播间信息失败，继续使用旧参数: {}", e),
+                }
+            }
+        }
+    }
+}
+
+// 带随机抖动的指数退避：在 [delay/2, delay] 之间随机取值，避免多个房间同时重连
+fn backoff_delay(attempt: u32) -> Duration {
+    let delay = RECONNECT_BASE
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(RECONNECT_MAX);
+    let half = delay.as_millis() as u64 / 2;
+    Duration::from_millis(half + rand::thread_rng().gen_range(0..=half))
+}
+
+// 用直播间页面重新获取 LiveInfo，得到新的连接参数
+async fn refresh_params(url: &str, old: &PushParams) -> Result<Refreshed, String> {
+    // 传进来的可能是直播间号或者带参数的地址，能直接看出 web_rid 时统一成直播间地址
+    let url = match resolver::parse_room_input(url) {
+        Ok(RoomLink::WebRid(web_rid)) => resolver::live_url(&web_rid),
+        _ => url.to_string(),
+    };
+    eprintln!("🔄 [WebSocket] 重新获取直播间信息: {}", url);
+    let info = DouYinReq::new(&url)
+        .get_room_info()
+        .await
+        .map_err(|e| e.to_string())?;
+    // 停播后连不上弹幕，不再重试
+    if !info.status.is_live() {
+        return Ok(Refreshed::LiveEnded);
+    }
+    let room_id = info.room.room_id.as_str();
+    let unique_id = if info.unique_id.is_empty() {
+        old.unique_id.clone()
+    } else {
+        info.unique_id
+    };
+    let ttwid = if info.ttwid.is_empty() {
+        old.ttwid.clone()
+    } else {
+        info.ttwid
+    };
+    if room_id != old.room_id {
+        eprintln!("🔄 [WebSocket] 房间号变了: {} -> {}", old.room_id, room_id);
+    }
+    Ok(Refreshed::Params(PushParams {
+        room_id: room_id.to_string(),
+        signature: signature::creat_signature(room_id, &unique_id),
+        unique_id,
+        ttwid,
+        room_url: old.room_url.clone(),
+    }))
+}
+
+// 建立一次连接并收消息，直到连接断开
+async fn connect_once(
+    params: &PushParams,
+    recorder: &mut Option<FrameRecorder>,
+    stream: &mut StreamState,
+    tx: &mpsc::UnboundedSender<SocketEvent>,
+    stop: &mut oneshot::Receiver<()>,
+) -> Disconnect {
+    eprintln!("🔌 [WebSocket] 正在连接到服务器，房间号: {}", params.room_id);
+    // 握手之前失败
+    let failed = |reason: String, rejected: bool| Disconnect::Lost {
+        reason,
+        rejected,
+        connected: false,
+        received: false,
+    };
+    // 连上之后断开
+    let lost = |reason: String, rejected: bool, received: bool| Disconnect::Lost {
+        reason,
+        rejected,
+        connected: true,
+        received,
+    };
+    if !stream.cursor.is_empty() {
+        eprintln!("🔌 [WebSocket] 从上次的位置继续: cursor={}", stream.cursor.cursor);
+    }
+    let mut request = match params.push_url(&stream.cursor).into_client_request() {
+        Ok(request) => request,
+        Err(e) => return failed(format!("连接地址无效: {}", e), true),
+    };
+    let headers = request.headers_mut();
+    headers.insert("user-agent", HeaderValue::from_static(USER_AGENT));
+    if !params.ttwid.is_empty() {
+        match HeaderValue::from_str(&format!("ttwid={}", params.ttwid)) {
+            Ok(cookie) => {
+                headers.insert("cookie", cookie);
+            }
+            Err(e) => return failed(format!("ttwid 无效: {}", e), true),
+        }
+    }
+
+    let socket = tokio::select! {
+        _ = &mut *stop => return Disconnect::Stopped,
+        result = tokio_tungstenite::connect_async(request) => match result {
+            Ok((socket, _)) => socket,
+            // 握手时服务器返回了 HTTP 错误，说明参数不被接受
+            Err(WsError::Http(response)) => {
+                return failed(format!("握手被拒绝: {}", response.status()), true)
+            }
+            Err(e) => return failed(format!("连接失败: {}", e), false),
+        },
+    };
+    eprintln!("✅ [WebSocket] 连接已建立！");
+    let _ = tx.send(SocketEvent::State(ConnectionState::Live));
+
+    let (mut write, mut read) = socket.split();
+    let mut heartbeat = interval_at(Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+    let mut last_received = Instant::now();
+    let mut received = false;
+    loop {
+        tokio::select! {
+            _ = &mut *stop => {
+                let _ = write.send(WsMessage::Close(None)).await;
+                return Disconnect::Stopped;
+            }
+            _ = heartbeat.tick() => {
+                if last_received.elapsed() > HEARTBEAT_TIMEOUT {
+                    return lost("心跳超时".to_string(), false, received);
+                }
+                if let Err(e) = write.send(WsMessage::Binary(heartbeat_frame())).await {
+                    return lost(format!("发送心跳失败: {}", e), false, received);
+                }
+            }
+            msg = read.next() => {
+                last_received = Instant::now();
+                match msg {
+                    Some(Ok(WsMessage::Binary(data))) => {
+                        received = true;
+                        if let Some(rec) = recorder.as_mut() {
+                            if let Err(e) = rec.write_frame(now_millis(), &data) {
+                                eprintln!("⚠️ [抓包] 写入失败，停止录制 {:?}: {}", rec.path(), e);
+                                *recorder = None;
+                            }
+                        }
+                        match handle_frame(&data, stream, tx) {
+                            Ok(handled) => {
+                                if let Some(ack) = handled.ack {
+                                    if let Err(e) = write.send(WsMessage::Binary(ack)).await {
+                                        return lost(format!("发送确认失败: {}", e), false, received);
+                                    }
+                                }
+                                if handled.live_ended {
+                                    let _ = write.send(WsMessage::Close(None)).await;
+                                    return Disconnect::LiveEnded;
+                                }
+                            }
+                            Err(e) => eprintln!("⚠️ [WebSocket] 解码消息失败: {}", e),
+                        }
+                    }
+                    Some(Ok(WsMessage::Ping(data))) => {
+                        if let Err(e) = write.send(WsMessage::Pong(data)).await {
+                            return lost(format!("回复 Pong 失败: {}", e), false, received);
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(frame))) => {
+                        let reason = frame
+                            .map(|f| f.reason.to_string())
+                            .filter(|reason| !reason.is_empty())
+                            .unwrap_or_else(|| "服务器关闭连接".to_string());
+                        // 刚连上还没收到消息就被关闭，多半是参数过期了
+                        return lost(reason, !received, received);
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return lost(format!("连接异常: {}", e), false, received),
+                    None => return lost("连接已结束".to_string(), !received, received),
+                }
+            }
+        }
+    }
+}
+
+// 按录制时的时间间隔重新解码抓包文件里的每一帧
+async fn replay(
+    path: &std::path::Path,
+    speed: f64,
+    tx: &mpsc::UnboundedSender<SocketEvent>,
+    mut stop: oneshot::Receiver<()>,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut reader = FrameReader::open(path)?;
+    eprintln!("📼 [回放] 房间号: {}, 倍速: {}", reader.room_id(), speed);
+    let _ = tx.send(SocketEvent::State(ConnectionState::Live));
+
+    let mut last_received: Option<u64> = None;
+    let mut count = 0usize;
+    let mut stream = StreamState::default();
+    while let Some(frame) = reader.next_frame()? {
+        let wait = match (last_received, speed > 0.0) {
+            (Some(last), true) => {
+                Duration::from_secs_f64(frame.received_at.saturating_sub(last) as f64 / 1000.0 / speed)
+            }
+            _ => Duration::ZERO,
+        };
+        tokio::select! {
+            _ = &mut stop => return Ok("主动停止回放".to_string()),
+            _ = tokio::time::sleep(wait) => {}
+        }
+        last_received = Some(frame.received_at);
+        count += 1;
+        // 回放时不需要回复 ack
+        if let Err(e) = handle_frame(&frame.data, &mut stream, tx) {
+            eprintln!("⚠️ [回放] 第 {} 帧解码失败: {}", count, e);
+        }
+    }
+    Ok(format!("回放结束，共 {} 帧", count))
+}
+
+// 跨越多次重连保留的接收状态：续传位置、最近的消息 id 和还没确认的断线
+#[derive(Default)]
+pub(super) struct StreamState {
+    pub(super) cursor: PushCursor,
+    recent: RecentIds,
+    last_message_at: Option<u64>,
+    // 断线前最后一次收到消息的时间和断线原因
+    pending_gap: Option<(u64, String)>,
+}
+
+impl StreamState {
+    // 连接断开时记下断线的位置，等重连后收到消息再判断有没有漏
+    fn mark_gap(&mut self, reason: &str) {
+        if self.pending_gap.is_some() {
+            return;
+        }
+        if let Some(from) = self.last_message_at {
+            self.pending_gap = Some((from, reason.to_string()));
+        }
+    }
+}
+
+// 最近收到的消息 id，超过容量时丢掉最早的
+#[derive(Default)]
+struct RecentIds {
+    ids: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl RecentIds {
+    // 记下消息 id，已经见过时返回 false
+    fn insert(&mut self, id: u64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > RECENT_ID_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+// 消息 id 优先取 payload 里 Common.msgId，没有时用外层的 msgId
+fn message_id(message: &proto::Message) -> u64 {
+    proto::decode_message::<douyin::MessageHead>(message)
+        .ok()
+        .and_then(|head| head.common)
+        .map(|common| common.msg_id)
+        .filter(|id| *id != 0)
+        .unwrap_or(message.msg_id as u64)
+}
+
+// 一帧数据的处理结果
+pub(super) struct HandledFrame {
+    // 需要回复给服务器的确认帧
+    pub ack: Option<Vec<u8>>,
+    // 帧里有直播结束的消息
+    pub live_ended: bool,
+}
+
+/// 解码一帧数据，去掉重复的消息后发送出去，同时更新续传位置
+pub(super) fn handle_frame(
+    data: &[u8],
+    stream: &mut StreamState,
+    tx: &mpsc::UnboundedSender<SocketEvent>,
+) -> Result<HandledFrame, Box<dyn std::error::Error + Send + Sync>> {
+    let mut handled = HandledFrame {
+        ack: None,
+        live_ended: false,
+    };
+    let frame = proto::decode_push_frame(data)?;
+    // 只有 msg 类型的帧里才有弹幕数据
+    if frame.payload_type != "msg" {
+        return Ok(handled);
+    }
+    let response = proto::decode_response(&frame)?;
+    if response.need_ack {
+        handled.ack = Some(ack_frame(frame.log_id, &response.internal_ext));
+    }
+    if !response.cursor.is_empty() || !response.internal_ext.is_empty() {
+        stream.cursor = PushCursor {
+            cursor: response.cursor,
+            internal_ext: response.internal_ext,
+            fetch_interval: response.fetch_interval,
+        };
+    }
+    if response.messages_list.is_empty() {
+        return Ok(handled);
+    }
+
+    let total = response.messages_list.len();
+    let fresh: Vec<proto::Message> = response
+        .messages_list
+        .into_iter()
+        .filter(|message| {
+            let id = message_id(message);
+            id == 0 || stream.recent.insert(id)
+        })
+        .collect();
+    let now = now_millis();
+    // 重连后的第一批消息里有之前收到过的，说明续传接上了，没有漏消息
+    if let Some((from, reason)) = stream.pending_gap.take() {
+        if fresh.len() < total {
+            eprintln!("✅ [WebSocket] 重连后消息是连续的");
+        } else {
+            eprintln!("⚠️ [WebSocket] 重连前后可能漏了消息: {} - {}", from, now);
+            let _ = tx.send(SocketEvent::Gap(MessageGap {
+                from,
+                to: now,
+                reason,
+            }));
+        }
+    }
+    stream.last_message_at = Some(now);
+
+    for message in fresh {
+        if message.method == "WebcastControlMessage" {
+            if let Ok(control) = proto::decode_message::<douyin::ControlMessage>(&message) {
+                handled.live_ended |= control.status == CONTROL_STATUS_ENDED;
+            }
+        }
+        let _ = tx.send(SocketEvent::Message(message));
+    }
+    Ok(handled)
+}
+
+/// 心跳帧：payloadType 为 hb 的空 PushFrame
+fn heartbeat_frame() -> Vec<u8> {
+    PushFrame {
+        payload_type: "hb".to_string(),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+/// 确认帧：带上原帧的 logId 和 Response 中的 internalExt
+fn ack_frame(log_id: u64, internal_ext: &str) -> Vec<u8> {
+    PushFrame {
+        payload_type: "ack".to_string(),
+        log_id,
+        payload: internal_ext.as_bytes().to_vec(),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn chat(msg_id: u64) -> proto::Message {
+        let chat = douyin::ChatMessage {
+            common: Some(douyin::Common {
+                msg_id,
+                ..Default::default()
+            }),
+            content: format!("第 {} 条", msg_id),
+            ..Default::default()
+        };
+        proto::Message {
+            method: "WebcastChatMessage".to_string(),
+            payload: chat.encode_to_vec(),
+            ..Default::default()
+        }
+    }
+
+    // 和服务器推送的一样：Response 用 gzip 压缩后放进 PushFrame
+    fn frame(ids: &[u64], cursor: &str) -> Vec<u8> {
+        let response = proto::Response {
+            messages_list: ids.iter().map(|id| chat(*id)).collect(),
+            cursor: cursor.to_string(),
+            fetch_interval: 1000,
+            internal_ext: format!("internal_src:dim|wss_push_room_id:100|cursor:{}", cursor),
+            need_ack: true,
+            ..Default::default()
+        };
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&response.encode_to_vec()).unwrap();
+        PushFrame {
+            log_id: 99,
+            payload_type: "msg".to_string(),
+            payload_encoding: "gzip".to_string(),
+            payload: gzip.finish().unwrap(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    // 处理一帧，返回这一帧产生的事件：消息给出 Common.msgId，Gap 给出 0
+    fn handle(stream: &mut StreamState, data: &[u8]) -> Vec<u64> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_frame(data, stream, &tx).unwrap();
+        drop(tx);
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(match event {
+                SocketEvent::Message(message) => message_id(&message),
+                SocketEvent::Gap(_) => 0,
+                SocketEvent::State(state) => panic!("不应该有状态事件: {:?}", state),
+            });
+        }
+        events
+    }
+
+    #[test]
+    fn drops_repeated_ids() {
+        let mut stream = StreamState::default();
+        assert_eq!(handle(&mut stream, &frame(&[1, 2, 3], "c1")), [1, 2, 3]);
+        assert_eq!(handle(&mut stream, &frame(&[3, 4, 2, 5], "c2")), [4, 5]);
+        assert!(handle(&mut stream, &frame(&[1, 5], "c3")).is_empty());
+    }
+
+    #[test]
+    fn cursor_from_response() {
+        let mut stream = StreamState::default();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let handled = handle_frame(&frame(&[1], "c1"), &mut stream, &tx).unwrap();
+        assert_eq!(stream.cursor.cursor, "c1");
+        assert_eq!(
+            stream.cursor.internal_ext,
+            "internal_src:dim|wss_push_room_id:100|cursor:c1"
+        );
+        assert_eq!(stream.cursor.fetch_interval, 1000);
+        assert!(handled.ack.is_some());
+        assert!(!handled.live_ended);
+
+        // 没有消息的帧也会更新续传位置
+        handle_frame(&frame(&[], "c2"), &mut stream, &tx).unwrap();
+        assert_eq!(stream.cursor.cursor, "c2");
+    }
+
+    #[test]
+    fn gap_only_without_overlap() {
+        let mut stream = StreamState::default();
+        handle(&mut stream, &frame(&[1, 2], "c1"));
+
+        // 重连后第一批里有收到过的消息，续传接上了
+        stream.mark_gap("连接断开");
+        assert_eq!(handle(&mut stream, &frame(&[2, 3], "c2")), [3]);
+        assert!(stream.pending_gap.is_none());
+
+        // 第一批全是新消息，中间可能漏了，Gap 在消息之前发出
+        stream.mark_gap("连接断开");
+        assert_eq!(handle(&mut stream, &frame(&[10, 11], "c3")), [0, 10, 11]);
+        // 之后的消息不再产生 Gap
+        assert_eq!(handle(&mut stream, &frame(&[12], "c4")), [12]);
+
+        // 还没收到过消息时断线，不知道从哪里开始漏，不记 Gap
+        let mut fresh = StreamState::default();
+        fresh.mark_gap("连接断开");
+        assert_eq!(handle(&mut fresh, &frame(&[1], "c1")), [1]);
+    }
+
+    #[test]
+    fn gap_reason_and_range() {
+        let mut stream = StreamState::default();
+        handle(&mut stream, &frame(&[1], "c1"));
+        let from = stream.last_message_at.unwrap();
+        stream.mark_gap("心跳超时");
+        // 重连过程中再次断开，保留第一次的原因
+        stream.mark_gap("握手失败");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handle_frame(&frame(&[2], "c2"), &mut stream, &tx).unwrap();
+        let Ok(SocketEvent::Gap(gap)) = rx.try_recv() else {
+            panic!("应该先收到 Gap");
+        };
+        assert_eq!(gap.from, from);
+        assert!(gap.to >= from);
+        assert_eq!(gap.reason, "心跳超时");
+    }
+
+    #[test]
+    fn recent_ids_evict_oldest() {
+        let mut recent = RecentIds::default();
+        for id in 1..=RECENT_ID_CAPACITY as u64 {
+            assert!(recent.insert(id));
+        }
+        assert!(!recent.insert(1));
+        // 超过容量后最早的 id 被丢掉，再来时当作新消息
+        assert!(recent.insert(RECENT_ID_CAPACITY as u64 + 1));
+        assert_eq!(recent.order.len(), RECENT_ID_CAPACITY);
+        assert!(recent.insert(1));
+        assert!(!recent.insert(RECENT_ID_CAPACITY as u64));
+    }
+
+    #[test]
+    fn evicted_ids_through_frames() {
+        let mut stream = StreamState::default();
+        let ids: Vec<u64> = (1..=RECENT_ID_CAPACITY as u64 + 1).collect();
+        assert_eq!(handle(&mut stream, &frame(&ids, "c1")).len(), ids.len());
+        // 1 被挤掉了，最新的还在
+        let last = ids.len() as u64;
+        assert_eq!(handle(&mut stream, &frame(&[1, last], "c2")), [1]);
+    }
+}
@@ -1,3 +1,5 @@
 fn main() {
-    tauri_build::build()
+    // 只编译命令行版本时不需要 Tauri
+    #[cfg(feature = "gui")]
+    tauri_build::build();
 }
@@ -0,0 +1,290 @@
+// 命令行版本：不需要界面，在服务器上监听直播间弹幕
+// 弹幕输出到标准输出（文本或每行一个 JSON），日志输出到标准错误
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use livebox_core::model::{LiveEvent, LiveEventPayload};
+use livebox_core::recorder::{RecordOptions, Recorder, StreamFormat};
+use livebox_core::resolver;
+use livebox_core::runner::DouYinReq;
+use livebox_core::server::broadcast::BroadcastServer;
+use livebox_core::signature;
+use livebox_core::stream::Quality;
+use livebox_core::utils::archive::Archive;
+use livebox_core::utils::forwarder::{ForwardConfig, Forwarder};
+use livebox_core::utils::push_queue::PushQueue;
+use livebox_core::utils::time::now_millis;
+use livebox_core::webcast::capture::{default_capture_path, FrameReader, FrameRecorder};
+use livebox_core::webcast::socket::{ConnectionState, LiveSocket, SocketEvent};
+use livebox_core::webcast::PushParams;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// 一行一条可读的文字
+    Text,
+    /// 每行一个 JSON，格式和前端收到的 live_event 一致
+    Json,
+}
+
+/// LiveBox 命令行版本，监听抖音直播间弹幕
+#[derive(Debug, Parser)]
+#[command(name = "livebox-cli", version)]
+struct Args {
+    /// 直播间地址、直播间号（live.douyin.com/ 后面的数字）、分享短链接或主播主页
+    #[arg(required_unless_present = "replay")]
+    room: Option<String>,
+
+    /// 输出格式
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// 推送地址，设置后把消息 POST 到这个地址
+    #[arg(long)]
+    push_url: Option<String>,
+
+    /// 推送的消息类型，逗号分隔（chat,gift,like,follow,comein）
+    #[arg(long, value_delimiter = ',', default_value = "chat,gift,like,follow,comein")]
+    push_types: Vec<String>,
+
+    /// 推送签名密钥
+    #[arg(long)]
+    push_secret: Option<String>,
+
+    /// 同时把原始数据录制到 ~/.livebox/captures
+    #[arg(long)]
+    record: bool,
+
+    /// 同时录制直播画面到 ~/.livebox/recordings，弹幕写到旁边的 .jsonl 文件
+    #[arg(long, conflicts_with = "replay")]
+    record_video: bool,
+
+    /// 录制的清晰度（origin、uhd、hd、sd、ld），没有时取更低的
+    #[arg(long, default_value = "origin")]
+    quality: Quality,
+
+    /// 录制用的拉流格式（flv、hls）
+    #[arg(long, default_value = "flv")]
+    video_format: StreamFormat,
+
+    /// 录制每段的分钟数，0 表示不按时长分段
+    #[arg(long, default_value_t = 30)]
+    segment_minutes: u64,
+
+    /// 录制每段的最大 MB，0 表示不按大小分段
+    #[arg(long, default_value_t = 0)]
+    segment_mb: u64,
+
+    /// 不保存到本地存档数据库
+    #[arg(long)]
+    no_archive: bool,
+
+    /// 在本机这个端口开启 WebSocket 广播服务，给 OBS 浏览器源等使用
+    #[arg(long)]
+    serve: Option<u16>,
+
+    /// 回放抓包文件，不连接直播间
+    #[arg(long, conflicts_with = "room")]
+    replay: Option<PathBuf>,
+
+    /// 回放倍速，0 表示不等待
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(args).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let archive = if args.no_archive {
+        None
+    } else {
+        Some(Archive::open_default()?)
+    };
+
+    // 推送队列和桌面端共用同一个文件，没推送完的消息下次启动会继续推送
+    let queue = Arc::new(PushQueue::open_default()?);
+    let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+    tokio::spawn(queue.clone().run(status_tx));
+    tokio::spawn(async move {
+        while let Some(status) = status_rx.recv().await {
+            if !status.ok {
+                eprintln!(
+                    "⚠️ [推送] {} 第 {} 次推送失败: {}",
+                    status.id,
+                    status.attempts,
+                    status.error.unwrap_or_default()
+                );
+            }
+        }
+    });
+    let forwarder = Forwarder::new(queue.clone());
+    if let Some(url) = args.push_url.clone() {
+        forwarder.set_config(ForwardConfig {
+            url,
+            types: args.push_types.clone(),
+            secret: args.push_secret.clone(),
+        });
+    }
+
+    let broadcast = match args.serve {
+        Some(port) => Some(BroadcastServer::bind(port).await?),
+        None => None,
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut video = None;
+    let (room_id, mut socket) = match (&args.replay, &args.room) {
+        (Some(path), _) => {
+            let room_id = FrameReader::open(path)?.room_id().to_string();
+            if let Some(archive) = &archive {
+                archive.begin_session(&room_id, &path.to_string_lossy(), "回放", "")?;
+            }
+            (room_id, LiveSocket::replay(path.clone(), args.speed, tx))
+        }
+        (None, Some(room)) => {
+            let url = resolver::resolve_room(room)
+                .await
+                .map_err(|e| e.to_string())?
+                .url;
+            let info = DouYinReq::new(&url).get_room_info().await?;
+            let room = &info.room;
+            if !info.status.is_live() {
+                let name = &room.owner.nickname;
+                return Err(format!("💤 {} 不在直播中: {}", name, info.status.label()).into());
+            }
+            let room_id = room.room_id.clone();
+            let title = room.title.as_str();
+            let anchor = room.owner.nickname.as_str();
+            eprintln!("🎯 直播间: {} - {} (房间号 {})", anchor, title, room_id);
+            if let Some(archive) = &archive {
+                archive.begin_session(&room_id, &url, title, anchor)?;
+            }
+
+            if args.record_video {
+                let options = RecordOptions {
+                    quality: args.quality,
+                    format: args.video_format,
+                    segment_secs: Some(args.segment_minutes * 60),
+                    segment_bytes: Some(args.segment_mb * 1024 * 1024),
+                    ..Default::default()
+                };
+                // 录制状态已经输出到日志，这里不需要再处理
+                let (state_tx, _) = mpsc::unbounded_channel();
+                let recorder = Recorder::start(
+                    &room_id,
+                    &info.streams,
+                    options,
+                    Some(url.clone()),
+                    state_tx,
+                )?;
+                video = Some(recorder);
+            }
+
+            let recorder = if args.record {
+                let path = default_capture_path(&room_id, now_millis());
+                Some(FrameRecorder::create(&path, &room_id)?)
+            } else {
+                None
+            };
+            let params = PushParams {
+                room_id: room_id.clone(),
+                signature: signature::creat_signature(&room_id, &info.unique_id),
+                unique_id: info.unique_id,
+                ttwid: info.ttwid,
+                room_url: Some(url),
+            };
+            (room_id, LiveSocket::connect(params, recorder, tx))
+        }
+        (None, None) => return Err("请指定直播间地址".into()),
+    };
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(SocketEvent::State(ConnectionState::Connecting)) => eprintln!("🔌 正在连接..."),
+                Some(SocketEvent::State(ConnectionState::Live)) => eprintln!("✅ 已连接"),
+                Some(SocketEvent::State(ConnectionState::Reconnecting { attempt, delay_ms, reason })) => {
+                    eprintln!("🔁 连接断开（{}），{} 毫秒后第 {} 次重连", reason, delay_ms, attempt);
+                }
+                Some(SocketEvent::Message(message)) => {
+                    let event = LiveEvent::from_message(&message);
+                    if let Some(archive) = &archive {
+                        if let Err(e) = archive.record(&room_id, &message, &event) {
+                            eprintln!("❌ [存档] 保存消息失败: {}", e);
+                        }
+                    }
+                    if let Some(server) = &broadcast {
+                        server.publish(&room_id, &event);
+                    }
+                    if let Some(video) = &video {
+                        video.record_event(&event);
+                    }
+                    forwarder.forward(&room_id, &message);
+                    print_event(&room_id, event, args.format)?;
+                }
+                Some(SocketEvent::Gap(gap)) => {
+                    eprintln!("⚠️ 重连前后可能漏了消息（{} - {}）", gap.from, gap.to);
+                    if let Some(archive) = &archive {
+                        archive.record_gap(&room_id, gap.from, gap.to, &gap.reason)?;
+                    }
+                }
+                Some(SocketEvent::State(ConnectionState::Ended { reason })) => {
+                    eprintln!("🔌 连接已断开: {}", reason);
+                    break;
+                }
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("🛑 收到退出信号");
+                socket.close();
+                break;
+            }
+        }
+    }
+    if let Some(archive) = &archive {
+        archive.end_session(&room_id)?;
+    }
+    queue.flush();
+    if let Some(mut video) = video {
+        video.stop();
+        if let Some(info) = video.info() {
+            eprintln!("🎬 录制了 {} 段，弹幕在 {}", info.segments.len(), info.sidecar_path);
+        }
+    }
+    Ok(())
+}
+
+fn print_event(
+    room_id: &str,
+    event: LiveEvent,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(&LiveEventPayload::new(room_id, event))?);
+        }
+        OutputFormat::Text => {
+            // 统计类消息在文字模式下不输出，避免刷屏
+            let Some(user) = event.user() else {
+                return Ok(());
+            };
+            let text = match &event {
+                LiveEvent::Like { count, .. } => format!("点赞 x{}", count),
+                LiveEvent::Member { .. } => "来了".to_string(),
+                LiveEvent::Follow { .. } => "关注了主播".to_string(),
+                _ => event.content().unwrap_or_default(),
+            };
+            let time = chrono::Local::now().format("%H:%M:%S");
+            println!("[{}] [{}] {}: {}", time, event.kind(), user.nick_name, text);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use livebox_core::utils::archive::{
+    Archive, ChatSearch, ChatSearchPage, EventPage, Session, SessionGap,
+};
+use livebox_core::utils::export::{self, ExportFormat, ExportSummary};
+use std::path::PathBuf;
+use tauri::State;
+
+// 采集会话列表，可以按房间过滤
+#[tauri::command]
+pub fn list_sessions(
+    room_id: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    archive: State<'_, Archive>,
+) -> Result<Vec<Session>, String> {
+    archive
+        .list_sessions(room_id.as_deref(), limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(|e| format!("读取会话列表失败: {}", e))
+}
+
+// 分页查看某次会话保存下来的消息
+#[tauri::command]
+pub fn get_session_events(
+    session_id: i64,
+    event_type: Option<String>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+    archive: State<'_, Archive>,
+) -> Result<EventPage, String> {
+    archive
+        .session_events(
+            session_id,
+            event_type.as_deref(),
+            page.unwrap_or(1),
+            page_size.unwrap_or(100),
+        )
+        .map_err(|e| format!("读取会话消息失败: {}", e))
+}
+
+// 某次会话里重连时可能漏掉消息的时间段
+#[tauri::command]
+pub fn get_session_gaps(
+    session_id: i64,
+    archive: State<'_, Archive>,
+) -> Result<Vec<SessionGap>, String> {
+    archive
+        .session_gaps(session_id)
+        .map_err(|e| format!("读取会话断线记录失败: {}", e))
+}
+
+// 搜索聊天记录：按关键词匹配内容和昵称，可以按房间、时间范围、用户过滤
+#[tauri::command]
+pub fn search_chat(
+    search: ChatSearch,
+    archive: State<'_, Archive>,
+) -> Result<ChatSearchPage, String> {
+    archive
+        .search_chat(&search)
+        .map_err(|e| format!("搜索聊天记录失败: {}", e))
+}
+
+// 导出某次会话的消息为 csv / jsonl / xlsx，不传 path 时保存到 ~/.livebox/exports
+#[tauri::command]
+pub fn export_session(
+    session_id: i64,
+    format: ExportFormat,
+    path: Option<String>,
+    archive: State<'_, Archive>,
+) -> Result<ExportSummary, String> {
+    let path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| export::default_export_path(session_id, format));
+    export::export_session(&archive, session_id, format, &path)
+        .map_err(|e| format!("导出会话失败: {}", e))
+}
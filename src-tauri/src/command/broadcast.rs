@@ -0,0 +1,72 @@
+use livebox_core::model::LiveEvent;
+use livebox_core::server::broadcast::BroadcastServer;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::State;
+
+// 本地 WebSocket 广播服务，没有开启时为 None
+#[derive(Default)]
+pub struct BroadcastState(pub Mutex<Option<BroadcastServer>>);
+
+impl BroadcastState {
+    /// 服务开启时把事件发给订阅者
+    pub fn publish(&self, room_id: &str, event: &LiveEvent) {
+        if let Ok(server) = self.0.lock() {
+            if let Some(server) = server.as_ref() {
+                server.publish(room_id, event);
+            }
+        }
+    }
+}
+
+// 广播服务的状态，给设置弹窗显示
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastStatus {
+    pub running: bool,
+    pub url: Option<String>,
+    pub subscribers: usize,
+}
+
+// 开启或关闭本地广播服务，端口变化时重新启动
+#[tauri::command]
+pub async fn set_broadcast_server(
+    enabled: bool,
+    port: u16,
+    state: State<'_, BroadcastState>,
+) -> Result<BroadcastStatus, String> {
+    let running_port = state
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|s| s.local_addr().port());
+    if !enabled {
+        state.0.lock().map_err(|e| e.to_string())?.take();
+    } else if running_port != Some(port) {
+        // 先停掉旧的服务再绑定，端口不变时不会冲突
+        state.0.lock().map_err(|e| e.to_string())?.take();
+        let server = BroadcastServer::bind(port)
+            .await
+            .map_err(|e| format!("启动本地推送服务失败: {}", e))?;
+        state.0.lock().map_err(|e| e.to_string())?.replace(server);
+    }
+    get_broadcast_status(state)
+}
+
+// 查看广播服务的地址和连接数
+#[tauri::command]
+pub fn get_broadcast_status(state: State<'_, BroadcastState>) -> Result<BroadcastStatus, String> {
+    let server = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(match server.as_ref() {
+        Some(server) => BroadcastStatus {
+            running: true,
+            url: Some(format!("ws://{}", server.local_addr())),
+            subscribers: server.subscriber_count(),
+        },
+        None => BroadcastStatus {
+            running: false,
+            url: None,
+            subscribers: 0,
+        },
+    })
+}
@@ -1,4 +1,4 @@
-use crate::utils::cookie_store::CookieStore;
+use livebox_core::utils::cookie_store::CookieStore;
 use tauri::{AppHandle, Manager};
 
 /// 保存用户提供的 cookie 字符串
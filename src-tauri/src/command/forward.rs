@@ -0,0 +1,49 @@
+use livebox_core::utils::forwarder::{ForwardConfig, Forwarder};
+use livebox_core::utils::push_queue::PushQueueSnapshot;
+use livebox_core::watcher::Watcher;
+use tauri::State;
+
+// 保存设置弹窗里的推送地址和消息类型，签名密钥同时用于开播通知
+#[tauri::command]
+pub fn set_push_config(
+    url: String,
+    types: Vec<String>,
+    secret: Option<String>,
+    forwarder: State<'_, Forwarder>,
+    watcher: State<'_, Watcher>,
+) -> Result<(), String> {
+    watcher.set_webhook_secret(secret.clone());
+    forwarder.set_config(ForwardConfig {
+        url: url.trim().to_string(),
+        types,
+        secret: secret.filter(|s| !s.is_empty()),
+    });
+    Ok(())
+}
+
+// 读取当前的推送配置
+#[tauri::command]
+pub fn get_push_config(forwarder: State<'_, Forwarder>) -> Result<ForwardConfig, String> {
+    Ok(forwarder.config())
+}
+
+// 查看推送队列：等待推送（含重试中）的消息和进入死信的消息
+#[tauri::command]
+pub fn get_push_queue(forwarder: State<'_, Forwarder>) -> Result<PushQueueSnapshot, String> {
+    forwarder
+        .queue()
+        .snapshot()
+        .map_err(|e| format!("读取推送队列失败: {}", e))
+}
+
+// 重新推送死信文件里的消息，不传 ids 时全部重新推送，返回重新推送的数量
+#[tauri::command]
+pub fn replay_push_failures(
+    ids: Option<Vec<String>>,
+    forwarder: State<'_, Forwarder>,
+) -> Result<usize, String> {
+    forwarder
+        .queue()
+        .replay(ids)
+        .map_err(|e| format!("重新推送失败: {}", e))
+}
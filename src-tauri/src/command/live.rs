@@ -1,5 +1,49 @@
-use crate::command::model::LiveInfo;
-use tauri::{AppHandle, Manager};
+use crate::command::broadcast::BroadcastState;
+use crate::command::record::RecordingState;
+use livebox_core::model::{LiveEvent, LiveEventPayload, LiveInfo};
+use livebox_core::resolver::{self, ResolvedRoom, RoomLink};
+use livebox_core::room::RoomInfo;
+use livebox_core::runner::DouYinReq;
+use livebox_core::stream::StreamSources;
+use livebox_core::signature;
+use livebox_core::utils::archive::Archive;
+use livebox_core::utils::forwarder::Forwarder;
+use livebox_core::utils::time::now_millis;
+use livebox_core::webcast::capture::{default_capture_path, FrameReader, FrameRecorder};
+use livebox_core::webcast::monitor::{MonitorInfo, MonitorManager, MonitorMeta, RoomEvent};
+use livebox_core::webcast::socket::{ConnectionState, MessageGap, SocketEvent};
+use livebox_core::webcast::PushParams;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+// 数据提取窗口的编号，同时打开多个直播间时窗口不会互相覆盖
+static DATA_WINDOW_SEQ: AtomicU32 = AtomicU32::new(0);
+
+// live_message 事件的内容，带上房间号方便前端区分
+#[derive(Serialize, Clone)]
+struct RoomMessage {
+    room_id: String,
+    message: livebox_core::proto::Message,
+}
+
+// live_status 事件的内容，连接状态的字段直接展开
+#[derive(Serialize, Clone)]
+struct RoomStatus {
+    room_id: String,
+    #[serde(flatten)]
+    state: ConnectionState,
+}
+
+// live_gap 事件的内容
+#[derive(Serialize, Clone)]
+struct RoomGap {
+    room_id: String,
+    #[serde(flatten)]
+    gap: MessageGap,
+}
 
 // 自定义函数
 #[tauri::command]
@@ -8,8 +52,47 @@ pub async fn greet_you(name: &str) -> Result<String, String> {
     Ok(format!("Hello, {}! You've been greeted from Rust!", name))
 }
 
+// 把输入的直播间号、短链接、主播主页等解析成 web_rid 和 room_id
+#[tauri::command]
+pub async fn resolve_room(input: String) -> Result<ResolvedRoom, String> {
+    resolver::resolve_room(&input)
+        .await
+        .map_err(|e| format!("解析直播间地址失败: {}", e))
+}
+
+// 获取直播间各个清晰度的 FLV / HLS 拉流地址，room 可以是直播间号、地址或者短链接
+#[tauri::command]
+pub async fn get_stream_urls(room: String) -> Result<StreamSources, String> {
+    let url = canonical_url(&room).await;
+    let info = DouYinReq::new(&url)
+        .get_room_info()
+        .await
+        .map_err(|e| format!("获取直播间信息失败: {}", e))?;
+    if !info.status.is_live() {
+        return Err(format!("直播间不在直播中: {}", info.status.label()));
+    }
+    Ok(info.streams)
+}
+
+// 先统一成 live.douyin.com/<web_rid>，解析失败时按原地址继续
+pub(crate) async fn canonical_url(url: &str) -> String {
+    match resolver::resolve_room(url).await {
+        Ok(resolved) => resolved.url,
+        Err(e) => {
+            println!("⚠️  [地址解析] 解析直播间地址失败，使用原地址: {}", e);
+            url.to_string()
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn get_live_html(url: &str, handle: AppHandle) -> Result<LiveInfo, String> {
+    let url = canonical_url(url).await;
+    fetch_live_info(&url, handle).await
+}
+
+// 打开直播间页面提取 LiveInfo，url 需要是 live.douyin.com 的地址
+async fn fetch_live_info(url: &str, handle: AppHandle) -> Result<LiveInfo, String> {
     println!("🎯 [get_live_html] 开始执行，URL: {}", url);
     println!("🌐 [get_live_html] 使用浏览器窗口提取数据（方案1）");
     println!("💡 [get_live_html] 不使用后端 HTTP 请求，直接在浏览器中提取数据");
@@ -94,9 +177,9 @@ pub async fn get_live_html(url: &str, handle: AppHandle) -> Result<LiveInfo, Str
         println!("💡 [get_live_html] 尝试从保存的 Cookie 文件中读取...");
 
         // 尝试从保存的 Cookie 文件中读取 ttwid
-        if let Ok(cookie_path) = crate::utils::cookie_store::CookieStore::get_default_path() {
+        if let Ok(cookie_path) = livebox_core::utils::cookie_store::CookieStore::get_default_path() {
             if cookie_path.exists() {
-                match crate::utils::cookie_store::CookieStore::load_from_file(&cookie_path) {
+                match livebox_core::utils::cookie_store::CookieStore::load_from_file(&cookie_path) {
                     Ok(store) => {
                         for cookie in &store.cookies {
                             if cookie.name == "ttwid" {
@@ -127,13 +210,11 @@ pub async fn get_live_html(url: &str, handle: AppHandle) -> Result<LiveInfo, Str
     }
 
     // ========== 步骤2: 打开浏览器窗口提取数据 ==========
-    let window_label = "douyinData";
-
-    // 如果窗口已存在，先关闭
-    if let Some(existing_window) = handle.get_window(window_label) {
-        let _ = existing_window.close();
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-    }
+    let window_label = format!(
+        "douyinData-{}",
+        DATA_WINDOW_SEQ.fetch_add(1, Ordering::Relaxed)
+    );
+    let window_label = window_label.as_str();
 
     // 创建窗口，注入数据提取脚本
     println!("🪟 [get_live_html] 步骤2: 打开浏览器窗口...");
@@ -233,9 +314,21 @@ pub async fn get_live_html(url: &str, handle: AppHandle) -> Result<LiveInfo, Str
                                             // 关闭窗口
                                             let _ = window.close();
 
+                                            // room_store 是 room 对象，解析成 RoomInfo，web_rid 从地址里取
+                                            let mut room = RoomInfo::from_json(&room_info).unwrap_or_default();
+                                            if let Ok(RoomLink::WebRid(web_rid)) = resolver::parse_room_input(url) {
+                                                room.web_rid = web_rid;
+                                            }
+
+                                            // 新建存档会话
+                                            begin_archive_session(&handle, url, &room);
+
                                             // 返回数据
                                             return Ok(LiveInfo {
                                                 room_info,
+                                                status: room.room_status(),
+                                                streams: room.stream_url.sources(),
+                                                room,
                                                 ttwid,
                                                 unique_id,
                                             });
@@ -333,3 +426,195 @@ pub async fn open_window(
         .unwrap();
     }
 }
+
+// 根据直播间信息新建一条存档会话，没有房间号时不记录
+fn begin_archive_session(handle: &AppHandle, url: &str, room: &RoomInfo) {
+    if room.room_id.is_empty() {
+        return;
+    }
+    if let Err(e) = handle
+        .state::<Archive>()
+        .begin_session(&room.room_id, url, &room.title, &room.owner.nickname)
+    {
+        println!("❌ [存档] 新建会话失败: {}", e);
+    }
+}
+
+// 在 Rust 端建立弹幕连接，原始消息通过 live_message 事件、解析后的事件通过 live_event 事件推送给前端
+// record 为 true 时同时把原始数据录制到抓包文件，返回抓包文件路径
+// 同一个房间重复连接时替换旧连接，其它房间的连接不受影响
+// 传入 room_url 时，重连被拒绝会用它重新获取直播间信息
+#[tauri::command]
+pub async fn connect_live(
+    room_id: String,
+    unique_id: String,
+    ttwid: String,
+    signature: Option<String>,
+    record: Option<bool>,
+    room_url: Option<String>,
+    state: State<'_, MonitorManager>,
+) -> Result<Option<String>, String> {
+    println!("🔌 [connect_live] 房间号: {}, unique_id: {}", room_id, unique_id);
+    // 前端没有传签名时，直接在 Rust 端生成
+    let signature = match signature {
+        Some(sign) if !sign.is_empty() => sign,
+        _ => signature::creat_signature(&room_id, &unique_id),
+    };
+    let params = PushParams {
+        room_id: room_id.clone(),
+        unique_id,
+        ttwid,
+        signature,
+        room_url,
+    };
+    let recorder = if record.unwrap_or(false) {
+        let path = default_capture_path(&room_id, now_millis());
+        Some(FrameRecorder::create(&path, &room_id).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+    let meta = MonitorMeta {
+        url: params.room_url.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+    let info = state.start(params, meta, recorder);
+    Ok(info.capture_path)
+}
+
+// 回放抓包文件，消息和真实连接一样经过解析、存档、推送，speed 为回放倍速，默认原速
+// 返回抓包文件里记录的房间号
+#[tauri::command]
+pub async fn replay_capture(
+    path: String,
+    speed: Option<f64>,
+    handle: AppHandle,
+    state: State<'_, MonitorManager>,
+) -> Result<String, String> {
+    let path = PathBuf::from(path);
+    let room_id = FrameReader::open(&path)
+        .map_err(|e| format!("打开抓包文件失败: {}", e))?
+        .room_id()
+        .to_string();
+    println!("📼 [replay_capture] 回放 {:?}，房间号: {}", path, room_id);
+    // 先建存档会话再开始回放，倍速为 0 时消息会立刻到达
+    if let Err(e) = handle.state::<Archive>().begin_session(
+        &room_id,
+        &path.to_string_lossy(),
+        "回放",
+        "",
+    ) {
+        println!("❌ [存档] 新建会话失败: {}", e);
+    }
+    let info = state
+        .replay(path, speed.unwrap_or(1.0))
+        .map_err(|e| format!("打开抓包文件失败: {}", e))?;
+    Ok(info.room_id)
+}
+
+// 开始监听一个直播间：获取房间信息后直接在 Rust 端连接，可以同时监听多个直播间
+#[tauri::command]
+pub async fn start_monitor(
+    url: String,
+    record: Option<bool>,
+    handle: AppHandle,
+    state: State<'_, MonitorManager>,
+) -> Result<MonitorInfo, String> {
+    println!("👀 [start_monitor] {}", url);
+    let url = canonical_url(&url).await;
+    let info = fetch_live_info(&url, handle.clone()).await?;
+    let room = &info.room;
+    if !info.status.is_live() {
+        return Err(format!("直播间不在直播中: {}", info.status.label()));
+    }
+    let room_id = room.room_id.as_str();
+    let meta = MonitorMeta {
+        url: url.clone(),
+        title: room.title.clone(),
+        anchor_name: room.owner.nickname.clone(),
+    };
+    let recorder = if record.unwrap_or(false) {
+        let path = default_capture_path(room_id, now_millis());
+        Some(FrameRecorder::create(&path, room_id).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+    let params = PushParams {
+        room_id: room_id.to_string(),
+        signature: signature::creat_signature(room_id, &info.unique_id),
+        unique_id: info.unique_id,
+        ttwid: info.ttwid,
+        room_url: Some(url),
+    };
+    Ok(state.start(params, meta, recorder))
+}
+
+// 停止监听一个直播间，返回这个房间之前是否在监听
+#[tauri::command]
+pub fn stop_monitor(room_id: String, state: State<'_, MonitorManager>) -> bool {
+    println!("🛑 [stop_monitor] 房间号: {}", room_id);
+    state.stop(&room_id)
+}
+
+// 正在监听的所有直播间
+#[tauri::command]
+pub fn list_monitors(state: State<'_, MonitorManager>) -> Vec<MonitorInfo> {
+    state.list()
+}
+
+// 处理所有直播间的消息：解析、存档、推送给前端和配置的地址，启动时调用一次
+pub fn spawn_event_pipeline(handle: AppHandle, mut rx: mpsc::UnboundedReceiver<RoomEvent>) {
+    let forwarder = handle.state::<Forwarder>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(RoomEvent { room_id, event }) = rx.recv().await {
+            match event {
+                SocketEvent::State(state) => {
+                    // 重连时存档会话不断开，直到彻底结束
+                    if let ConnectionState::Ended { .. } = state {
+                        let _ = handle.state::<Archive>().end_session(&room_id);
+                    }
+                    let _ = handle.emit_all("live_status", RoomStatus { room_id, state });
+                }
+                SocketEvent::Message(message) => {
+                    // 解析好的结构化事件，供外部工具和后续功能使用
+                    let event = LiveEvent::from_message(&message);
+                    if let Err(e) = handle.state::<Archive>().record(&room_id, &message, &event) {
+                        println!("❌ [存档] 保存消息失败: {}", e);
+                    }
+                    handle.state::<BroadcastState>().publish(&room_id, &event);
+                    // 正在录制时写到旁路文件，和视频对齐
+                    handle.state::<RecordingState>().record_event(&room_id, &event);
+                    let _ = handle.emit_all("live_event", LiveEventPayload::new(&room_id, event));
+                    // 按设置推送到配置的地址
+                    forwarder.forward(&room_id, &message);
+                    let _ = handle.emit_all("live_message", RoomMessage { room_id, message });
+                }
+                SocketEvent::Gap(gap) => {
+                    if let Err(e) = handle.state::<Archive>().record_gap(
+                        &room_id,
+                        gap.from,
+                        gap.to,
+                        &gap.reason,
+                    ) {
+                        println!("❌ [存档] 保存断线记录失败: {}", e);
+                    }
+                    let _ = handle.emit_all("live_gap", RoomGap { room_id, gap });
+                }
+            }
+        }
+    });
+}
+
+// 断开弹幕连接，不传房间号时断开所有直播间
+#[tauri::command]
+pub fn disconnect_live(room_id: Option<String>, state: State<'_, MonitorManager>) {
+    match room_id {
+        Some(room_id) => {
+            println!("🔌 [disconnect_live] 断开房间 {}", room_id);
+            state.stop(&room_id);
+        }
+        None => {
+            let count = state.stop_all();
+            println!("🔌 [disconnect_live] 断开全部 {} 个连接", count);
+        }
+    }
+}
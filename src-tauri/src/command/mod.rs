@@ -1,4 +1,7 @@
+pub mod archive;
+pub mod broadcast;
 pub mod cookie;
+pub mod forward;
 pub mod live;
-pub mod model;
-pub mod runner;
+pub mod record;
+pub mod watch;
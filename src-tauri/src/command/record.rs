@@ -0,0 +1,116 @@
+use crate::command::live::canonical_url;
+use livebox_core::model::LiveEvent;
+use livebox_core::recorder::{RecordInfo, RecordOptions, RecordState, Recorder, StreamFormat};
+use livebox_core::runner::DouYinReq;
+use livebox_core::stream::Quality;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+// 正在录制的直播间，key 是 room_id
+#[derive(Default)]
+pub struct RecordingState(pub Mutex<HashMap<String, Recorder>>);
+
+impl RecordingState {
+    /// 这个房间正在录制时把事件写到旁路文件
+    pub fn record_event(&self, room_id: &str, event: &LiveEvent) {
+        if let Ok(recorders) = self.0.lock() {
+            if let Some(recorder) = recorders.get(room_id) {
+                recorder.record_event(event);
+            }
+        }
+    }
+}
+
+// record_status 事件的内容，带上房间号方便前端区分
+#[derive(Serialize, Clone)]
+struct RecordStatus {
+    room_id: String,
+    #[serde(flatten)]
+    state: RecordState,
+}
+
+// 开始录制直播流，room 可以是直播间号、地址或者短链接
+//
+// segment_secs / segment_mb 控制分段，sidecar_types 是写进旁路文件的事件类型，不传时全部写入，传空数组时不写
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_recording(
+    room: String,
+    quality: Option<Quality>,
+    format: Option<StreamFormat>,
+    segment_secs: Option<u64>,
+    segment_mb: Option<u64>,
+    sidecar_types: Option<Vec<String>>,
+    handle: AppHandle,
+    state: State<'_, RecordingState>,
+) -> Result<RecordInfo, String> {
+    let url = canonical_url(&room).await;
+    println!("🎬 [start_recording] {}", url);
+    let info = DouYinReq::new(&url)
+        .get_room_info()
+        .await
+        .map_err(|e| format!("获取直播间信息失败: {}", e))?;
+    if !info.status.is_live() {
+        return Err(format!("直播间不在直播中: {}", info.status.label()));
+    }
+
+    let defaults = RecordOptions::default();
+    let options = RecordOptions {
+        quality: quality.unwrap_or(defaults.quality),
+        format: format.unwrap_or(defaults.format),
+        segment_secs: segment_secs.or(defaults.segment_secs),
+        segment_bytes: segment_mb.map(|mb| mb * 1024 * 1024),
+        sidecar_types,
+        ..defaults
+    };
+    let room_id = info.room.room_id.clone();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let recorder = Recorder::start(&room_id, &info.streams, options, Some(url), tx)
+        .map_err(|e| format!("开始录制失败: {}", e))?;
+    let record_info = recorder.info().ok_or("录制状态不可用")?;
+    spawn_status_relay(handle, room_id.clone(), rx);
+
+    // 同一个房间重复开始时停掉之前的录制
+    let previous = state
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(room_id, recorder);
+    drop(previous);
+    Ok(record_info)
+}
+
+// 停止录制，返回之前是否在录制这个房间
+#[tauri::command]
+pub fn stop_recording(room_id: String, state: State<'_, RecordingState>) -> Result<bool, String> {
+    println!("⏹️  [stop_recording] {}", room_id);
+    let recorder = state.0.lock().map_err(|e| e.to_string())?.remove(&room_id);
+    Ok(recorder.is_some())
+}
+
+// 所有录制和它们的文件、状态
+#[tauri::command]
+pub fn list_recordings(state: State<'_, RecordingState>) -> Result<Vec<RecordInfo>, String> {
+    let recorders = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(recorders.values().filter_map(Recorder::info).collect())
+}
+
+// 把录制状态转发给前端
+fn spawn_status_relay(
+    handle: AppHandle,
+    room_id: String,
+    mut rx: mpsc::UnboundedReceiver<RecordState>,
+) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(state) = rx.recv().await {
+            let status = RecordStatus {
+                room_id: room_id.clone(),
+                state,
+            };
+            let _ = handle.emit_all("record_status", status);
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use livebox_core::utils::archive::Archive;
+use livebox_core::watcher::{WatchChange, WatchEvent, WatchInfo, Watcher};
+use livebox_core::webcast::monitor::{MonitorManager, MonitorMeta};
+use livebox_core::webcast::socket::ConnectionState;
+use tauri::api::notification::Notification;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+// 关注一个直播间，开播时通知；auto_start 为 true 时开播后自动开始监听弹幕
+#[tauri::command]
+pub async fn add_watch(
+    url: String,
+    interval_secs: Option<u64>,
+    auto_start: Option<bool>,
+    watcher: State<'_, Watcher>,
+) -> Result<WatchInfo, String> {
+    println!("⭐ [add_watch] {}", url);
+    watcher
+        .add(&url, interval_secs, auto_start.unwrap_or(false))
+        .await
+}
+
+// 取消关注，返回之前是否关注了这个直播间
+#[tauri::command]
+pub fn remove_watch(web_rid: String, watcher: State<'_, Watcher>) -> bool {
+    watcher.remove(&web_rid)
+}
+
+// 关注的直播间和最近一次检查的状态
+#[tauri::command]
+pub fn list_watches(watcher: State<'_, Watcher>) -> Vec<WatchInfo> {
+    watcher.list()
+}
+
+// 设置开播通知的推送地址，传空字符串时关闭
+#[tauri::command]
+pub fn set_watch_webhook(url: String, watcher: State<'_, Watcher>) {
+    watcher.set_webhook(Some(url));
+}
+
+#[tauri::command]
+pub fn get_watch_webhook(watcher: State<'_, Watcher>) -> Option<String> {
+    watcher.webhook()
+}
+
+// 处理开播、下播事件：通知前端、弹出系统通知，需要时自动开始监听
+pub fn spawn_watch_pipeline(handle: AppHandle, mut rx: mpsc::UnboundedReceiver<WatchEvent>) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            notify(&handle, &event);
+            if event.change == WatchChange::GoLive && event.room.target.auto_start {
+                auto_start(&handle, &event);
+            }
+            let _ = handle.emit_all("watch_event", event);
+        }
+    });
+}
+
+fn notify(handle: &AppHandle, event: &WatchEvent) {
+    let room = &event.room;
+    let name = if room.anchor_name.is_empty() {
+        room.target.web_rid.as_str()
+    } else {
+        room.anchor_name.as_str()
+    };
+    let (title, body) = match event.change {
+        WatchChange::GoLive => (format!("{} 开播了", name), room.title.clone()),
+        WatchChange::GoOffline => (format!("{} 下播了", name), String::new()),
+    };
+    let identifier = handle.config().tauri.bundle.identifier.clone();
+    if let Err(e) = Notification::new(identifier).title(title).body(body).show() {
+        println!("⚠️  [关注] 系统通知失败: {}", e);
+    }
+}
+
+// 开播时用检查得到的参数直接连接，这个房间正在监听时不重复连接
+// 上一场结束后留下的 Ended 记录不算，start 会直接替换掉
+fn auto_start(handle: &AppHandle, event: &WatchEvent) {
+    let Some(params) = event.params.clone() else {
+        return;
+    };
+    let monitors = handle.state::<MonitorManager>();
+    let listening = monitors
+        .get(&params.room_id)
+        .is_some_and(|info| !matches!(info.status, ConnectionState::Ended { .. }));
+    if listening {
+        return;
+    }
+    let room = &event.room;
+    println!("▶️  [关注] {} 开播，自动开始监听", room.target.url);
+    if let Err(e) = handle.state::<Archive>().begin_session(
+        &params.room_id,
+        &room.target.url,
+        &room.title,
+        &room.anchor_name,
+    ) {
+        println!("❌ [存档] 新建会话失败: {}", e);
+    }
+    let meta = MonitorMeta {
+        url: room.target.url.clone(),
+        title: room.title.clone(),
+        anchor_name: room.anchor_name.clone(),
+    };
+    monitors.start(params, meta, None);
+}
@@ -1,23 +1,55 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::sync::Arc;
 use tauri::Manager;
+use tokio::sync::mpsc;
+
+use livebox_core::utils;
 
 // 对command单独管理
 mod command;
-mod utils;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_websocket::init())
+        .manage(command::broadcast::BroadcastState::default())
+        .manage(command::record::RecordingState::default())
         .invoke_handler(tauri::generate_handler![
             command::live::get_live_html,
+            command::live::resolve_room,
+            command::live::get_stream_urls,
             command::live::greet_you,
             command::live::open_window,
+            command::live::connect_live,
+            command::live::disconnect_live,
+            command::live::replay_capture,
+            command::live::start_monitor,
+            command::live::stop_monitor,
+            command::live::list_monitors,
+            command::watch::add_watch,
+            command::watch::remove_watch,
+            command::watch::list_watches,
+            command::watch::set_watch_webhook,
+            command::watch::get_watch_webhook,
+            command::record::start_recording,
+            command::record::stop_recording,
+            command::record::list_recordings,
             command::cookie::save_cookies,
             command::cookie::load_cookies,
             command::cookie::clear_cookies,
-            command::cookie::open_login_page
+            command::cookie::open_login_page,
+            command::forward::set_push_config,
+            command::forward::get_push_config,
+            command::forward::get_push_queue,
+            command::forward::replay_push_failures,
+            command::archive::list_sessions,
+            command::archive::get_session_events,
+            command::archive::get_session_gaps,
+            command::archive::search_chat,
+            command::archive::export_session,
+            command::broadcast::set_broadcast_server,
+            command::broadcast::get_broadcast_status
         ])
         .on_window_event(|event| {
             // 当主窗口被关闭时，如果登录窗口在运行，则隐藏主窗口而不是退出
@@ -58,6 +90,32 @@ fn main() {
 
             println!("🛡️ 守护窗口已创建，应用不会自动退出");
 
+            // 消息存档
+            app.manage(utils::archive::Archive::open_default()?);
+
+            // 消息推送，推送结果通过 push_status 事件通知前端
+            let queue = Arc::new(utils::push_queue::PushQueue::open_default()?);
+            let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+            tauri::async_runtime::spawn(queue.clone().run(status_tx));
+            app.manage(utils::forwarder::Forwarder::new(queue));
+            let app_handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                while let Some(status) = status_rx.recv().await {
+                    let _ = app_handle.emit_all("push_status", status);
+                }
+            });
+
+            // 直播间监听，所有房间的消息汇总到同一条处理流程
+            let (monitors, room_rx) = livebox_core::webcast::monitor::MonitorManager::new();
+            app.manage(monitors);
+            command::live::spawn_event_pipeline(app.handle(), room_rx);
+
+            // 关注的直播间，开播、下播时通知，需要时自动开始监听
+            let (watcher, watch_rx) = livebox_core::watcher::Watcher::open_default();
+            app.manage(watcher.clone());
+            command::watch::spawn_watch_pipeline(app.handle(), watch_rx);
+            tauri::async_runtime::spawn(async move { watcher.start() });
+
             // 启动时检查 Cookie 文件状态
             use utils::cookie_store::CookieStore;
             if let Ok(cookie_path) = CookieStore::get_default_path() {
@@ -94,9 +152,18 @@ fn main() {
                         if let Some(daemon) = app_handle.get_window("daemon") {
                             let _ = daemon.close();
                         }
+                        flush_push_queue(app_handle);
                     }
                 }
+                tauri::RunEvent::Exit => flush_push_queue(app_handle),
                 _ => {}
             }
         });
 }
+
+// 推送队列每秒才写一次文件，退出前把最后入队或重试的消息写进去
+fn flush_push_queue(app_handle: &tauri::AppHandle) {
+    if let Some(forwarder) = app_handle.try_state::<utils::forwarder::Forwarder>() {
+        forwarder.queue().flush();
+    }
+}
@@ -1 +0,0 @@
-pub mod cookie_store;
@@ -1,5 +1,25 @@
+// 直播间状态，和 Rust 端 RoomStatus 一致
+export type RoomStatus = 'preparing' | 'live' | 'paused' | 'ended' | 'banned' | 'not_found'
+
+// 一种清晰度的拉流地址，quality 是 origin / uhd / hd / sd / ld
+export interface StreamSource {
+    quality: string
+    name: string
+    resolution: string
+    flv: string | null
+    hls: string | null
+}
+
+export interface StreamSources {
+    default_quality: string | null
+    sources: StreamSource[]
+}
+
 export interface LiveInfoImp {
     room_info: string
+    room: any
+    status: RoomStatus
+    streams: StreamSources
     ttwid: string
     unique_id: string
 }